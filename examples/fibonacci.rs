@@ -0,0 +1,46 @@
+//! Times `test.lox`-style Fibonacci loops through the interpreter.
+//!
+//! Run with `cargo run --release --example fibonacci [iterations]`.
+
+use std::env;
+use std::time::Instant;
+
+fn main() {
+    let iterations: usize = env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(100_000);
+
+    let source = format!(
+        "
+        var a = 0;
+        var b = 1;
+        var temp = 0;
+        var i = 0;
+        while (i < {}) {{
+          temp = a;
+          a = b;
+          b = temp + b;
+          i = i + 1;
+        }}
+
+        for (var n = 0; n < {}; n = n + 1) {{
+          temp = a;
+          a = b;
+          b = temp + b;
+        }}
+        ",
+        iterations, iterations
+    );
+
+    let start = Instant::now();
+    loxc::execute(&source).expect("benchmark program failed");
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} loop iterations in {:?} ({:?} per iteration)",
+        iterations * 2,
+        elapsed,
+        elapsed / (iterations as u32 * 2)
+    );
+}
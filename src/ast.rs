@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::ops::{Index, IndexMut};
+
+use crate::expr::Expr;
+use crate::span::Span;
+use crate::statement::Statement;
+
+/// Handle to an [`Expr`] stored in an [`Ast`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExprId(u32);
+
+/// Handle to a [`Statement`] stored in an [`Ast`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StmtId(u32);
+
+impl ExprId {
+    /// Dense index of this node, usable as a key into side tables.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl StmtId {
+    /// Dense index of this node, usable as a key into side tables.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The clauses of a `for` loop, which the parser desugars into a `while`
+/// loop wrapped in blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForLoop {
+    pub initializer: Option<StmtId>,
+    pub condition: Option<ExprId>,
+    pub increment: Option<ExprId>,
+    pub body: StmtId,
+}
+
+/// Arena owning every expression and statement of a parsed program.
+///
+/// Nodes refer to their children through [`ExprId`]s and [`StmtId`]s, so
+/// extra information about a node (resolved scopes, types, spans, ...) can be
+/// kept in a separate `Vec` indexed by the node's id instead of in the tree.
+#[derive(Debug, Default, Clone)]
+pub struct Ast {
+    exprs: Vec<Expr>,
+    statements: Vec<Statement>,
+    expr_spans: Vec<Span>,
+    statement_spans: Vec<Span>,
+    /// The `for` loops found in the source, keyed by the outermost statement
+    /// they were desugared into.
+    for_loops: HashMap<StmtId, ForLoop>,
+}
+
+impl Ast {
+    pub fn add_expr(&mut self, expr: Expr, span: Span) -> ExprId {
+        let id = ExprId(u32::try_from(self.exprs.len()).expect("too many expressions"));
+        self.exprs.push(expr);
+        self.expr_spans.push(span);
+        id
+    }
+
+    pub fn add_statement(&mut self, statement: Statement, span: Span) -> StmtId {
+        let id = StmtId(u32::try_from(self.statements.len()).expect("too many statements"));
+        self.statements.push(statement);
+        self.statement_spans.push(span);
+        id
+    }
+
+    /// Source code the expression was parsed from.
+    pub fn expr_span(&self, id: ExprId) -> Span {
+        self.expr_spans[id.index()]
+    }
+
+    /// Source code the statement was parsed from.
+    pub fn statement_span(&self, id: StmtId) -> Span {
+        self.statement_spans[id.index()]
+    }
+
+    pub fn add_for_loop(&mut self, id: StmtId, for_loop: ForLoop) {
+        self.for_loops.insert(id, for_loop);
+    }
+
+    /// The `for` loop `id` was desugared from, if any.
+    pub fn for_loop(&self, id: StmtId) -> Option<&ForLoop> {
+        self.for_loops.get(&id)
+    }
+
+    pub fn expr_count(&self) -> usize {
+        self.exprs.len()
+    }
+
+    pub fn statement_count(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Renders an expression as an S-expression, e.g. `(+ 1 (group 2))`.
+    pub fn expr_to_string(&self, id: ExprId) -> String {
+        match &self[id] {
+            Expr::Unary(operator, expression) => {
+                format!("({} {})", operator, self.expr_to_string(*expression))
+            }
+            Expr::Literal(lit) => format!("{}", lit),
+            Expr::Binary(left, operator, right) => format!(
+                "({} {} {})",
+                operator,
+                self.expr_to_string(*left),
+                self.expr_to_string(*right)
+            ),
+            Expr::Grouping(expression) => {
+                format!("(group {})", self.expr_to_string(*expression))
+            }
+            Expr::Variable(token) => token.lexeme.to_string(),
+            Expr::Assign(token, expression) => {
+                format!("(= {} {})", token.lexeme, self.expr_to_string(*expression))
+            }
+            Expr::Logical(left, operator, right) => format!(
+                "({} {} {})",
+                operator,
+                self.expr_to_string(*left),
+                self.expr_to_string(*right)
+            ),
+        }
+    }
+
+    /// Renders a statement as an S-expression, e.g. `(if c (print 1) (block))`.
+    pub fn statement_to_string(&self, id: StmtId) -> String {
+        match &self[id] {
+            Statement::Print(expression) => {
+                format!("(print {})", self.expr_to_string(*expression))
+            }
+            Statement::Expression(expression) => {
+                format!("(expr {})", self.expr_to_string(*expression))
+            }
+            Statement::Var(name, None) => format!("(var {})", name.lexeme),
+            Statement::Var(name, Some(initializer)) => format!(
+                "(var {} {})",
+                name.lexeme,
+                self.expr_to_string(*initializer)
+            ),
+            Statement::Block(statements) => {
+                let mut out = String::from("(block");
+                for statement in statements {
+                    out.push(' ');
+                    out.push_str(&self.statement_to_string(*statement));
+                }
+                out.push(')');
+                out
+            }
+            Statement::If(condition, then_branch, None) => format!(
+                "(if {} {})",
+                self.expr_to_string(*condition),
+                self.statement_to_string(*then_branch)
+            ),
+            Statement::If(condition, then_branch, Some(else_branch)) => format!(
+                "(if {} {} {})",
+                self.expr_to_string(*condition),
+                self.statement_to_string(*then_branch),
+                self.statement_to_string(*else_branch)
+            ),
+            Statement::While(condition, body) => format!(
+                "(while {} {})",
+                self.expr_to_string(*condition),
+                self.statement_to_string(*body)
+            ),
+        }
+    }
+}
+
+impl Index<ExprId> for Ast {
+    type Output = Expr;
+
+    fn index(&self, id: ExprId) -> &Expr {
+        &self.exprs[id.index()]
+    }
+}
+
+impl IndexMut<ExprId> for Ast {
+    fn index_mut(&mut self, id: ExprId) -> &mut Expr {
+        &mut self.exprs[id.index()]
+    }
+}
+
+impl Index<StmtId> for Ast {
+    type Output = Statement;
+
+    fn index(&self, id: StmtId) -> &Statement {
+        &self.statements[id.index()]
+    }
+}
+
+impl IndexMut<StmtId> for Ast {
+    fn index_mut(&mut self, id: StmtId) -> &mut Statement {
+        &mut self.statements[id.index()]
+    }
+}
@@ -0,0 +1,332 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Arbitrary-precision signed integer.
+///
+/// The magnitude is stored as little-endian base 2^32 limbs without trailing
+/// zero limbs, so zero is an empty magnitude and is never negative.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    pub fn from_i64(n: i64) -> BigInt {
+        let mut result = BigInt::from_u64(n.unsigned_abs());
+        result.negative = n < 0;
+        result.normalized()
+    }
+
+    pub fn from_u64(n: u64) -> BigInt {
+        BigInt {
+            negative: false,
+            magnitude: vec![n as u32, (n >> 32) as u32],
+        }
+        .normalized()
+    }
+
+    /// Parses unsigned `digits` in the given radix.
+    pub fn parse(digits: &str, radix: u32) -> Option<BigInt> {
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix)?;
+            mul_add_small(&mut magnitude, radix, digit);
+        }
+        Some(
+            BigInt {
+                negative: false,
+                magnitude,
+            }
+            .normalized(),
+        )
+    }
+
+    /// Returns `10^exponent`.
+    pub fn pow10(exponent: u32) -> BigInt {
+        let mut magnitude = vec![1];
+        for _ in 0..exponent {
+            mul_add_small(&mut magnitude, 10, 0);
+        }
+        BigInt {
+            negative: false,
+            magnitude,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_positive(&self) -> bool {
+        !self.negative && !self.is_zero()
+    }
+
+    /// Number of base 2^32 limbs in the magnitude.
+    pub fn limbs(&self) -> usize {
+        self.magnitude.len()
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.magnitude.first().is_some_and(|limb| limb & 1 == 1)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt {
+            negative: false,
+            magnitude: self.magnitude.clone(),
+        }
+    }
+
+    /// Truncating division, returning the quotient and a remainder with the
+    /// sign of `self`. Returns `None` when dividing by zero.
+    pub fn div_rem(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &divisor.magnitude);
+        let quotient = BigInt {
+            negative: self.negative != divisor.negative,
+            magnitude: quotient,
+        };
+        let remainder = BigInt {
+            negative: self.negative,
+            magnitude: remainder,
+        };
+        Some((quotient.normalized(), remainder.normalized()))
+    }
+
+    fn normalized(mut self) -> BigInt {
+        while self.magnitude.last() == Some(&0) {
+            self.magnitude.pop();
+        }
+        if self.magnitude.is_empty() {
+            self.negative = false;
+        }
+        self
+    }
+}
+
+/// `magnitude = magnitude * factor + addend`
+fn mul_add_small(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = u64::from(addend);
+    for limb in magnitude.iter_mut() {
+        // At most (2^32 - 1)^2 + 2^32 - 1, which fits in 64 bits.
+        #[allow(clippy::arithmetic_side_effects)]
+        let product = u64::from(*limb) * u64::from(factor) + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+    if carry != 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = Vec::with_capacity(long.len().saturating_add(1));
+    let mut carry = 0u64;
+    for (i, limb) in long.iter().enumerate() {
+        // Two limbs and a carry of at most 1 fit in 64 bits.
+        #[allow(clippy::arithmetic_side_effects)]
+        let sum = u64::from(*limb) + u64::from(*short.get(i).unwrap_or(&0)) + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    if carry != 0 {
+        result.push(carry as u32);
+    }
+    result
+}
+
+/// `a - b`, where `a >= b`.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = false;
+    for (i, limb) in a.iter().enumerate() {
+        let (difference, below) = limb.overflowing_sub(*b.get(i).unwrap_or(&0));
+        let (difference, below_again) = difference.overflowing_sub(u32::from(borrow));
+        borrow = below || below_again;
+        result.push(difference);
+    }
+    result
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+
+    // Both lengths are bounded by memory, far below `usize::MAX`.
+    #[allow(clippy::arithmetic_side_effects)]
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        // The partial product of `x` starts at limb `i`.
+        let row = &mut result[i..];
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            // At most (2^32 - 1)^2 + 2 * (2^32 - 1), which fits in 64 bits.
+            #[allow(clippy::arithmetic_side_effects)]
+            let product = u64::from(*x) * u64::from(*y) + u64::from(row[j]) + carry;
+            row[j] = product as u32;
+            carry = product >> 32;
+        }
+        row[b.len()] = carry as u32;
+    }
+    result
+}
+
+/// Schoolbook binary long division of two magnitudes.
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitude(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    let bits = a.len().saturating_mul(32);
+    for bit in (0..bits).rev() {
+        // remainder = remainder << 1 | next bit of a
+        mul_add_small(&mut remainder, 2, (a[bit / 32] >> (bit % 32)) & 1);
+        if cmp_magnitude(&remainder, b) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, b);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (quotient, remainder)
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt {
+                negative: self.negative,
+                magnitude: add_magnitude(&self.magnitude, &other.magnitude),
+            }
+            .normalized();
+        }
+
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt {
+                negative: other.negative,
+                magnitude: sub_magnitude(&other.magnitude, &self.magnitude),
+            },
+            _ => BigInt {
+                negative: self.negative,
+                magnitude: sub_magnitude(&self.magnitude, &other.magnitude),
+            },
+        }
+        .normalized()
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    #[allow(clippy::arithmetic_side_effects)]
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt {
+            negative: self.negative != other.negative,
+            magnitude: mul_magnitude(&self.magnitude, &other.magnitude),
+        }
+        .normalized()
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt {
+            negative: !self.negative,
+            magnitude: self.magnitude.clone(),
+        }
+        .normalized()
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u64 = 1_000_000_000;
+
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off nine decimal digits at a time, least significant first.
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let mut remainder = 0u64;
+            for limb in magnitude.iter_mut().rev() {
+                let value = (remainder << 32) | u64::from(*limb);
+                *limb = (value / CHUNK) as u32;
+                remainder = value % CHUNK;
+            }
+            while magnitude.last() == Some(&0) {
+                magnitude.pop();
+            }
+            chunks.push(remainder);
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+
+use crate::parser::Parser;
+use crate::span::Span;
+use crate::token::token::Token;
+use crate::token::token_type::TokenType;
+use crate::tokenizer::Tokenizer;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and carriage returns.
+    Whitespace,
+    Newline,
+    /// A `//` comment, without the newline ending it.
+    Comment,
+    /// Source the tokenizer could not turn into a token, such as an
+    /// unexpected character or an unterminated string.
+    Skipped,
+}
+
+/// Source text between two tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// Splits the text between two tokens into trivia. `line` and `column` are
+/// where `start` is.
+pub fn split_trivia(
+    source: &str,
+    start: usize,
+    end: usize,
+    mut line: usize,
+    mut column: usize,
+) -> Vec<Trivia> {
+    let mut trivia = Vec::new();
+    let mut offset = start;
+    while offset < end {
+        let rest = &source[offset..end];
+        let c = rest.chars().next().unwrap();
+        let (kind, length) = if c == '\n' {
+            (TriviaKind::Newline, 1)
+        } else if c == ' ' || c == '\t' || c == '\r' {
+            let length = rest
+                .find(|c: char| !matches!(c, ' ' | '\t' | '\r'))
+                .unwrap_or(rest.len());
+            (TriviaKind::Whitespace, length)
+        } else if rest.starts_with("//") {
+            (TriviaKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else {
+            let length = rest
+                .char_indices()
+                .find(|(i, c)| *i > 0 && (c.is_whitespace() || rest[*i..].starts_with("//")))
+                .map_or(rest.len(), |(i, _)| i);
+            (TriviaKind::Skipped, length)
+        };
+
+        // Every piece of trivia ends within `source`.
+        #[allow(clippy::arithmetic_side_effects)]
+        let end = offset + length;
+        let text = &source[offset..end];
+        trivia.push(Trivia {
+            kind,
+            text: text.to_string(),
+            span: Span {
+                start: offset,
+                end,
+                line,
+                column,
+            },
+        });
+        if kind == TriviaKind::Newline {
+            line = line.saturating_add(1);
+            column = 1;
+        } else {
+            column = column.saturating_add(text.chars().count());
+        }
+        offset = end;
+    }
+    trivia
+}
+
+/// What a [`Node`] of the concrete syntax tree is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Program,
+    VarDeclaration,
+    PrintStatement,
+    ExpressionStatement,
+    Block,
+    IfStatement,
+    WhileStatement,
+    ForStatement,
+    AssignExpr,
+    LogicalExpr,
+    BinaryExpr,
+    UnaryExpr,
+    GroupingExpr,
+    LiteralExpr,
+    VariableExpr,
+    /// A declaration with a syntax error, and the tokens skipped after it.
+    Error,
+}
+
+/// A token of the concrete syntax tree, with the trivia before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub token_type: TokenType,
+    pub text: String,
+    pub span: Span,
+    pub leading_trivia: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element {
+    Node(Node),
+    Token(SyntaxToken),
+}
+
+/// A node of the concrete syntax tree. Unlike the AST it keeps every token
+/// and all trivia, so printing it gives back the source byte for byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: SyntaxKind,
+    pub children: Vec<Element>,
+}
+
+impl Node {
+    /// The tokens under this node, in source order.
+    pub fn tokens(&self) -> Vec<&SyntaxToken> {
+        let mut tokens = Vec::new();
+        for child in &self.children {
+            match child {
+                Element::Node(node) => tokens.extend(node.tokens()),
+                Element::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// The child nodes, skipping tokens.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Element::Node(node) => Some(node),
+            Element::Token(_) => None,
+        })
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                Element::Node(node) => write!(f, "{}", node)?,
+                Element::Token(token) => {
+                    for trivia in &token.leading_trivia {
+                        write!(f, "{}", trivia.text)?;
+                    }
+                    write!(f, "{}", token.text)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Collects the concrete syntax tree while the [`Parser`] runs.
+///
+/// The parser hands over every token it consumes, and once it has parsed a
+/// construct it wraps everything consumed since a [`CstBuilder::checkpoint`]
+/// into a node. Constructs abandoned because of a syntax error are simply
+/// not wrapped, so their tokens end up in an enclosing node.
+#[derive(Debug, Default)]
+pub struct CstBuilder {
+    /// Leading trivia of each token, indexed like the parser's tokens.
+    pub trivia: Vec<Vec<Trivia>>,
+    /// Finished children of the nodes not wrapped yet.
+    elements: Vec<Element>,
+}
+
+impl CstBuilder {
+    pub fn new(trivia: Vec<Vec<Trivia>>) -> CstBuilder {
+        CstBuilder {
+            trivia,
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn token(&mut self, index: usize, token: &Token) {
+        let leading_trivia = self
+            .trivia
+            .get_mut(index)
+            .map(std::mem::take)
+            .unwrap_or_default();
+        self.elements.push(Element::Token(SyntaxToken {
+            token_type: token.token_type,
+            text: token.lexeme.to_string(),
+            span: token.span,
+            leading_trivia,
+        }));
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Makes everything since `checkpoint` the children of a new node.
+    pub fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        let children = self.elements.split_off(checkpoint);
+        self.elements.push(Element::Node(Node { kind, children }));
+    }
+
+    pub fn finish(self) -> Node {
+        Node {
+            kind: SyntaxKind::Program,
+            children: self.elements,
+        }
+    }
+}
+
+/// A concrete syntax tree, and the first error found while building it.
+pub struct SyntaxTree {
+    pub root: Node,
+    pub error: Option<Error>,
+}
+
+/// Parses `source` into a lossless syntax tree. A tree is built even for
+/// source with errors: whatever could not be parsed ends up in
+/// [`SyntaxKind::Error`] nodes or as [`TriviaKind::Skipped`] trivia.
+pub fn parse(source: &str) -> SyntaxTree {
+    let mut tokenizer = Tokenizer {
+        source,
+        keep_trivia: true,
+        ..Default::default()
+    };
+    let tokenize_result = tokenizer.scan_tokens();
+
+    let mut parser = Parser {
+        tokens: tokenizer.tokens,
+        cst: Some(CstBuilder::new(tokenizer.trivia)),
+        ..Default::default()
+    };
+    let parse_result = parser.parse();
+
+    let error = match (tokenize_result, parse_result) {
+        (Err(errors), _) => Some(Error::TokenizingError(errors)),
+        (Ok(()), Err(errors)) => Some(Error::ParseError(errors)),
+        (Ok(()), Ok(_)) => None,
+    };
+    SyntaxTree {
+        root: parser.cst.take().unwrap_or_default().finish(),
+        error,
+    }
+}
@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
+
+use crate::bigint::BigInt;
+
+/// How to round a decimal result that has more digits than can be kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Round to nearest, ties to the even neighbour (banker's rounding).
+    #[default]
+    HalfEven,
+    /// Round to nearest, ties away from zero.
+    HalfUp,
+    /// Round towards zero.
+    Down,
+    /// Round away from zero.
+    Up,
+    /// Round towards negative infinity.
+    Floor,
+    /// Round towards positive infinity.
+    Ceiling,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-even" => Ok(Rounding::HalfEven),
+            "half-up" => Ok(Rounding::HalfUp),
+            "down" => Ok(Rounding::Down),
+            "up" => Ok(Rounding::Up),
+            "floor" => Ok(Rounding::Floor),
+            "ceiling" => Ok(Rounding::Ceiling),
+            _ => Err(format!("Unknown rounding mode: {}", s)),
+        }
+    }
+}
+
+/// Settings for decimal operations that cannot always be exact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalContext {
+    /// Digits kept after the decimal point when dividing.
+    pub scale: u32,
+    pub rounding: Rounding,
+}
+
+impl Default for DecimalContext {
+    fn default() -> Self {
+        DecimalContext {
+            scale: 20,
+            rounding: Rounding::default(),
+        }
+    }
+}
+
+/// Exact decimal number: `coefficient / 10^scale`.
+///
+/// The scale of a literal is kept, so `1.50d` prints as `1.50`, while
+/// equality and ordering compare numeric values.
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    coefficient: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn from_bigint(n: BigInt) -> Decimal {
+        Decimal {
+            coefficient: n,
+            scale: 0,
+        }
+    }
+
+    /// Parses unsigned decimal digits with an optional fractional part.
+    pub fn parse(text: &str) -> Option<Decimal> {
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let coefficient = BigInt::parse(&format!("{}{}", integer, fraction), 10)?;
+        Some(Decimal {
+            coefficient,
+            scale: u32::try_from(fraction.len()).ok()?,
+        })
+    }
+
+    pub fn coefficient(&self) -> &BigInt {
+        &self.coefficient
+    }
+
+    /// Digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.coefficient.is_negative()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.coefficient.is_positive()
+    }
+
+    /// Divides, keeping `context.scale` fractional digits rounded with
+    /// `context.rounding` and dropping trailing zeros. Returns `None` when
+    /// dividing by zero.
+    pub fn divide(&self, other: &Decimal, context: &DecimalContext) -> Option<Decimal> {
+        if other.coefficient.is_zero() {
+            return None;
+        }
+
+        // self / other * 10^scale = (a * 10^(scale + sb - sa)) / b, where
+        // three 32-bit scales cannot overflow 64 bits.
+        #[allow(clippy::arithmetic_side_effects)]
+        let shift = i64::from(context.scale) + i64::from(other.scale) - i64::from(self.scale);
+        // `BigInt` arithmetic cannot overflow, and neither can any below.
+        #[allow(clippy::arithmetic_side_effects)]
+        let (numerator, denominator) = if shift >= 0 {
+            let shift = u32::try_from(shift).ok()?;
+            (
+                &self.coefficient * &BigInt::pow10(shift),
+                other.coefficient.clone(),
+            )
+        } else {
+            let shift = u32::try_from(shift.unsigned_abs()).ok()?;
+            (
+                self.coefficient.clone(),
+                &other.coefficient * &BigInt::pow10(shift),
+            )
+        };
+
+        let (quotient, remainder) = numerator.div_rem(&denominator)?;
+        let negative = numerator.is_negative() != denominator.is_negative();
+        let coefficient = round(
+            quotient,
+            &remainder,
+            &denominator,
+            negative,
+            context.rounding,
+        );
+        Some(
+            Decimal {
+                coefficient,
+                scale: context.scale,
+            }
+            .trimmed(),
+        )
+    }
+
+    /// Multiplies exactly, unless the product needs more fractional digits
+    /// than either operand and `context.scale`. It is then rounded to the
+    /// largest of those with `context.rounding`, dropping trailing zeros, so
+    /// repeated multiplication does not double the scale every time. Returns
+    /// `None` if the scale overflows.
+    pub fn multiply(&self, other: &Decimal, context: &DecimalContext) -> Option<Decimal> {
+        #[allow(clippy::arithmetic_side_effects)]
+        let coefficient = &self.coefficient * &other.coefficient;
+        let scale = self.scale.checked_add(other.scale)?;
+        let limit = context.scale.max(self.scale).max(other.scale);
+        if scale <= limit {
+            return Some(Decimal { coefficient, scale });
+        }
+
+        // `scale` is above `limit` here.
+        #[allow(clippy::arithmetic_side_effects)]
+        let divisor = BigInt::pow10(scale - limit);
+        let (quotient, remainder) = coefficient.div_rem(&divisor)?;
+        let coefficient = round(
+            quotient,
+            &remainder,
+            &divisor,
+            coefficient.is_negative(),
+            context.rounding,
+        );
+        Some(
+            Decimal {
+                coefficient,
+                scale: limit,
+            }
+            .trimmed(),
+        )
+    }
+
+    /// Callers pass a `scale` at least as large as `self.scale`.
+    #[allow(clippy::arithmetic_side_effects)]
+    fn rescaled(&self, scale: u32) -> BigInt {
+        &self.coefficient * &BigInt::pow10(scale - self.scale)
+    }
+
+    fn trimmed(mut self) -> Decimal {
+        let ten = BigInt::from_i64(10);
+        while let Some(scale) = self.scale.checked_sub(1) {
+            match self.coefficient.div_rem(&ten) {
+                Some((quotient, remainder)) if remainder.is_zero() => {
+                    self.coefficient = quotient;
+                    self.scale = scale;
+                }
+                _ => break,
+            }
+        }
+        self
+    }
+}
+
+/// Rounds a truncated `quotient` given the `remainder` of the division.
+fn round(
+    quotient: BigInt,
+    remainder: &BigInt,
+    divisor: &BigInt,
+    negative: bool,
+    rounding: Rounding,
+) -> BigInt {
+    if remainder.is_zero() {
+        return quotient;
+    }
+
+    #[allow(clippy::arithmetic_side_effects)]
+    let twice_remainder = &remainder.abs() * &BigInt::from_i64(2);
+    let half = twice_remainder.cmp(&divisor.abs());
+    let away_from_zero = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => true,
+        Rounding::HalfUp => half != Ordering::Less,
+        Rounding::HalfEven => {
+            half == Ordering::Greater || (half == Ordering::Equal && quotient.is_odd())
+        }
+        Rounding::Floor => negative,
+        Rounding::Ceiling => !negative,
+    };
+
+    if !away_from_zero {
+        return quotient;
+    }
+    let step = BigInt::from_i64(if negative { -1 } else { 1 });
+    #[allow(clippy::arithmetic_side_effects)]
+    let rounded = &quotient + &step;
+    rounded
+}
+
+impl Add for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: &Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        #[allow(clippy::arithmetic_side_effects)]
+        let coefficient = &self.rescaled(scale) + &other.rescaled(scale);
+        Decimal { coefficient, scale }
+    }
+}
+
+impl Sub for &Decimal {
+    type Output = Decimal;
+
+    #[allow(clippy::arithmetic_side_effects)]
+    fn sub(self, other: &Decimal) -> Decimal {
+        self + &-other
+    }
+}
+
+impl Neg for &Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        #[allow(clippy::arithmetic_side_effects)]
+        let coefficient = -&self.coefficient;
+        Decimal {
+            coefficient,
+            scale: self.scale,
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescaled(scale).cmp(&other.rescaled(scale))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.coefficient.abs().to_string();
+        let scale = self.scale as usize;
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        if scale == 0 {
+            return write!(f, "{}", digits);
+        }
+
+        // Padding to `scale + 1` digits leaves at least one integer digit.
+        #[allow(clippy::arithmetic_side_effects)]
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        #[allow(clippy::arithmetic_side_effects)]
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}.{}", integer, fraction)
+    }
+}
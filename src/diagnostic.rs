@@ -0,0 +1,146 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+
+use crate::span::Span;
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether rendered diagnostics use ANSI colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when stderr is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("Unknown color choice: {}", s)),
+        }
+    }
+}
+
+/// How bad a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Severity {
+    /// The source cannot be run.
+    #[default]
+    Error,
+    /// The source runs but probably has a mistake, as reported by `loxc lint`.
+    Warning,
+}
+
+/// An error about a piece of source code, rendered like rustc does:
+///
+/// ```text
+/// error[E0001]: Unexpected character: $
+///  --> script.lox:1:7
+///   |
+/// 1 | print $;
+///   |       ^
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable error code such as `E0001`, printed as `error[E0001]`.
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub span: Span,
+    /// Short text printed after the underline.
+    pub label: Option<String>,
+    /// Notes printed below the snippet as `= help: ...`.
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn render(&self, file_name: &str, source: &str, color: bool) -> String {
+        let paint = |style: &'static str| if color { style } else { "" };
+        let (red, blue, bold, reset) = (paint(RED), paint(BLUE), paint(BOLD), paint(RESET));
+        let (severity, accent) = match self.severity {
+            Severity::Error => ("error", red),
+            Severity::Warning => ("warning", paint(YELLOW)),
+        };
+
+        let line_number = self.span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let mut out = String::new();
+
+        let code = match self.code {
+            Some(code) => format!("[{}]", code),
+            None => String::new(),
+        };
+        out.push_str(&format!(
+            "{accent}{severity}{code}{reset}{bold}: {}{reset}\n",
+            self.message
+        ));
+        out.push_str(&format!(
+            "{gutter}{blue}-->{reset} {}:{}:{}\n",
+            file_name, self.span.line, self.span.column
+        ));
+        out.push_str(&format!("{gutter} {blue}|{reset}\n"));
+
+        let line = source
+            .split('\n')
+            .nth(self.span.line.saturating_sub(1))
+            .unwrap_or("");
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        out.push_str(&format!("{blue}{line_number} |{reset} {}\n", line));
+
+        // Keep tabs in the indentation so the carets line up with the text.
+        let indent: String = line
+            .chars()
+            .take(self.span.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = source
+            .get(self.span.start..self.span.end)
+            .map_or(0, |text| {
+                text.split('\n').next().unwrap_or("").chars().count()
+            })
+            .max(1);
+        let label = self
+            .label
+            .as_ref()
+            .map_or(String::new(), |label| format!(" {}", label));
+        out.push_str(&format!(
+            "{gutter} {blue}|{reset} {indent}{accent}{}{}{reset}\n",
+            "^".repeat(width),
+            label
+        ));
+
+        if !self.help.is_empty() {
+            out.push_str(&format!("{gutter} {blue}|{reset}\n"));
+        }
+        for help in &self.help {
+            out.push_str(&format!(
+                "{gutter} {blue}={reset} {bold}help{reset}: {}\n",
+                help
+            ));
+        }
+        out.push('\n');
+        out
+    }
+}
@@ -1,34 +1,23 @@
 use std::fmt;
 
+use crate::ast::ExprId;
+use crate::bigint::BigInt;
+use crate::decimal::Decimal;
+use crate::interner::Symbol;
 use crate::token::token::Token;
 
 #[derive(Debug, Clone)]
 pub enum Expr {
-    Unary(UnaryOp, Box<Expr>),
+    Unary(UnaryOp, ExprId),
     Literal(Literal),
-    Binary(Box<Expr>, BinaryOp, Box<Expr>),
-    Grouping(Box<Expr>),
+    Binary(ExprId, BinaryOp, ExprId),
+    Grouping(ExprId),
     Variable(Token),
-    Assign(Token, Box<Expr>),
-    Logical(Box<Expr>, LogicalOp, Box<Expr>),
+    Assign(Token, ExprId),
+    Logical(ExprId, LogicalOp, ExprId),
 }
 
-impl fmt::Display for Expr {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let message = match self {
-            Expr::Unary(operator, expression) => format!("({} {})", operator, expression),
-            Expr::Literal(lit) => format!("{}", lit),
-            Expr::Binary(left, operator, right) => format!("({} {} {})", operator, left, right),
-            Expr::Grouping(expression) => format!("(group {})", expression),
-            Expr::Variable(token) => format!("{}", token),
-            Expr::Assign(token, expression) => format!("{} {}", token, expression),
-            Expr::Logical(left, operator, right) => format!("{} {} {}", left, operator, right),
-        };
-        write!(f, "{}", message)
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum LogicalOp {
     Or,
     And,
@@ -37,13 +26,13 @@ pub enum LogicalOp {
 impl fmt::Display for LogicalOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            LogicalOp::Or => write!(f, "||"),
-            LogicalOp::And => write!(f, "&&"),
+            LogicalOp::Or => write!(f, "or"),
+            LogicalOp::And => write!(f, "and"),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum BinaryOp {
     Plus,
     Minus,
@@ -76,7 +65,7 @@ impl fmt::Display for BinaryOp {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum UnaryOp {
     Minus,
     Bang,
@@ -93,8 +82,10 @@ impl fmt::Display for UnaryOp {
 
 #[derive(Debug, Clone)]
 pub enum Literal {
-    // TODO: Convert this to a `&'a str`. See https://github.com/koddsson/codecrafters-interpreter-rust/pull/1#discussion_r1684154612
-    Str(String),
+    Str(Symbol),
+    Int(i64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Number(f64),
     Nil,
     False,
@@ -104,7 +95,11 @@ pub enum Literal {
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let message = match self {
-            Literal::Str(str) => str.to_string(),
+            Literal::Str(str) => format!("\"{}\"", str),
+            // Printed like whole floats, as `parse` always has.
+            Literal::Int(n) => format!("{}.0", n),
+            Literal::BigInt(n) => format!("{}n", n),
+            Literal::Decimal(n) => format!("{}d", n),
             Literal::Number(n) => {
                 if n.fract() == 0.0 {
                     format!("{}.0", n)
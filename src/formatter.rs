@@ -0,0 +1,356 @@
+use crate::ast::{Ast, ExprId, StmtId};
+use crate::expr::Expr;
+use crate::parser::Parser;
+use crate::span::Span;
+use crate::statement::Statement;
+use crate::tokenizer::Tokenizer;
+use crate::Error;
+
+/// Spaces per indentation level.
+const INDENT: &str = "    ";
+/// Lines longer than this are wrapped before binary operators.
+const MAX_WIDTH: usize = 80;
+
+/// Re-emits `source` in the canonical style: four-space indentation, one
+/// statement per line, spaces around binary operators and opening braces on
+/// the line of their statement. Comments and single blank lines between
+/// statements are kept.
+pub fn format(source: &str) -> Result<String, Error> {
+    let mut tokenizer = Tokenizer {
+        source,
+        ..Default::default()
+    };
+    tokenizer.scan_tokens().map_err(Error::TokenizingError)?;
+    let comments = std::mem::take(&mut tokenizer.comments);
+
+    let mut parser = Parser {
+        tokens: tokenizer.tokens,
+        ..Default::default()
+    };
+    let statements = parser.parse().map_err(Error::ParseError)?;
+
+    let mut formatter = Formatter {
+        source,
+        ast: &parser.ast,
+        comments,
+        next_comment: 0,
+        last_end: 0,
+        indent: 0,
+        out: String::new(),
+    };
+    for statement in statements {
+        formatter.statement(statement);
+    }
+    formatter.leading_comments(source.len());
+    Ok(formatter.out)
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    ast: &'a Ast,
+    comments: Vec<Span>,
+    /// First comment not written yet.
+    next_comment: usize,
+    /// End of the last statement or comment written, to tell whether the
+    /// source had a blank line before the next one.
+    last_end: usize,
+    indent: usize,
+    out: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Keeps one blank line before the item starting at `start` if the source
+    /// had any.
+    fn blank_line_before(&mut self, start: usize) {
+        let at_block_start = self.out.is_empty() || self.out.ends_with("{\n");
+        let gap = &self.source[self.last_end.min(start)..start];
+        if !at_block_start && gap.matches('\n').count() > 1 {
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes the comments starting before `offset` on lines of their own.
+    fn leading_comments(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment).copied() {
+            if comment.start >= offset {
+                break;
+            }
+            self.next_comment = self.next_comment.saturating_add(1);
+            self.blank_line_before(comment.start);
+            self.write_indent();
+            self.out
+                .push_str(self.source[comment.start..comment.end].trim_end());
+            self.out.push('\n');
+            self.last_end = comment.end;
+        }
+    }
+
+    /// Appends a comment that follows `end` on the same source line. Returns
+    /// whether there was one.
+    fn trailing_comment(&mut self, end: usize) -> bool {
+        let Some(comment) = self.comments.get(self.next_comment).copied() else {
+            return false;
+        };
+        let gap = self.source.get(end..comment.start).unwrap_or("\n");
+        if gap.contains('\n') || !gap.trim().is_empty() {
+            return false;
+        }
+        self.next_comment = self.next_comment.saturating_add(1);
+        self.out.push(' ');
+        self.out
+            .push_str(self.source[comment.start..comment.end].trim_end());
+        self.last_end = comment.end;
+        true
+    }
+
+    /// Moves the comments left inside the statement written from `start` in
+    /// the output, which ends at `end` in the source, to the end of its first
+    /// line, since the line breaks that ended them are not kept.
+    fn inner_comments(&mut self, start: usize, end: usize) {
+        let mut comments = String::new();
+        while let Some(comment) = self.comments.get(self.next_comment).copied() {
+            if comment.start >= end {
+                break;
+            }
+            self.next_comment = self.next_comment.saturating_add(1);
+            comments.push(' ');
+            comments.push_str(self.source[comment.start..comment.end].trim_end());
+        }
+        if comments.is_empty() {
+            return;
+        }
+
+        // The first line break outside a string literal, which has no escapes.
+        let mut in_string = false;
+        let line_end = self.out[start..]
+            .char_indices()
+            .find(|(_, c)| {
+                if *c == '"' {
+                    in_string = !in_string;
+                }
+                *c == '\n' && !in_string
+            })
+            .map_or(self.out.len(), |(i, _)| start.saturating_add(i));
+        self.out.insert_str(line_end, &comments);
+    }
+
+    /// Writes a statement on lines of its own, with its comments.
+    fn statement(&mut self, id: StmtId) {
+        let span = self.ast.statement_span(id);
+        self.leading_comments(span.start);
+        self.blank_line_before(span.start);
+        self.write_indent();
+        let start = self.out.len();
+        self.statement_body(id);
+        self.inner_comments(start, span.end);
+        self.last_end = span.end;
+        self.trailing_comment(span.end);
+        self.out.push('\n');
+    }
+
+    /// Writes a statement starting at the current position, without the
+    /// final newline.
+    fn statement_body(&mut self, id: StmtId) {
+        if let Some(for_loop) = self.ast.for_loop(id).copied() {
+            let initializer = match for_loop.initializer {
+                Some(initializer) => self.clause(initializer),
+                None => String::from(";"),
+            };
+            let mut header = format!("for ({}", initializer);
+            if let Some(condition) = for_loop.condition {
+                header.push(' ');
+                header.push_str(&self.expr(condition));
+            }
+            header.push(';');
+            if let Some(increment) = for_loop.increment {
+                header.push(' ');
+                header.push_str(&self.expr(increment));
+            }
+            header.push(')');
+            self.out.push_str(&header);
+            self.body(for_loop.body);
+            return;
+        }
+
+        match &self.ast[id] {
+            Statement::Print(expression) => {
+                let text = self.wrapped("print ", *expression);
+                self.out.push_str(&format!("print {};", text));
+            }
+            Statement::Expression(expression) => {
+                let text = self.wrapped("", *expression);
+                self.out.push_str(&format!("{};", text));
+            }
+            Statement::Var(name, None) => self.out.push_str(&format!("var {};", name.lexeme)),
+            Statement::Var(name, Some(initializer)) => {
+                let prefix = format!("var {} = ", name.lexeme);
+                let text = self.wrapped(&prefix, *initializer);
+                self.out.push_str(&format!("{}{};", prefix, text));
+            }
+            Statement::Block(statements) => {
+                let statements = statements.clone();
+                self.block(&statements, self.ast.statement_span(id));
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                let (then_branch, else_branch) = (*then_branch, *else_branch);
+                self.out
+                    .push_str(&format!("if ({})", self.expr(*condition)));
+                let then_is_block = self.body(then_branch);
+                let Some(else_branch) = else_branch else {
+                    return;
+                };
+
+                let span = self.ast.statement_span(then_branch);
+                if then_is_block && !self.trailing_comment(span.end) {
+                    self.out.push(' ');
+                } else {
+                    self.out.push('\n');
+                    self.write_indent();
+                }
+                self.out.push_str("else");
+                if let Statement::If(..) = self.ast[else_branch] {
+                    if self.ast.for_loop(else_branch).is_none() {
+                        self.out.push(' ');
+                        self.statement_body(else_branch);
+                        return;
+                    }
+                }
+                self.body(else_branch);
+            }
+            Statement::While(condition, body) => {
+                let body = *body;
+                self.out
+                    .push_str(&format!("while ({})", self.expr(*condition)));
+                self.body(body);
+            }
+        }
+    }
+
+    /// Writes the body of an `if`, `else`, `while` or `for`: blocks go on the
+    /// same line, anything else on the next one, indented. Returns whether
+    /// the body was a block.
+    fn body(&mut self, id: StmtId) -> bool {
+        if let (Statement::Block(statements), None) = (&self.ast[id], self.ast.for_loop(id)) {
+            let statements = statements.clone();
+            self.out.push(' ');
+            self.block(&statements, self.ast.statement_span(id));
+            return true;
+        }
+
+        self.out.push('\n');
+        self.indent = self.indent.saturating_add(1);
+        let span = self.ast.statement_span(id);
+        // No blank line between the header and the body.
+        self.last_end = span.start;
+        self.leading_comments(span.start);
+        self.write_indent();
+        let start = self.out.len();
+        self.statement_body(id);
+        self.inner_comments(start, span.end);
+        self.last_end = span.end;
+        self.trailing_comment(span.end);
+        self.indent = self.indent.saturating_sub(1);
+        false
+    }
+
+    fn block(&mut self, statements: &[StmtId], span: Span) {
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.start < span.end);
+        if statements.is_empty() && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        let after_brace = span.start.saturating_add(1);
+        self.last_end = after_brace;
+        self.trailing_comment(after_brace);
+        self.out.push('\n');
+        self.indent = self.indent.saturating_add(1);
+        for statement in statements {
+            self.statement(*statement);
+        }
+        self.leading_comments(span.end);
+        self.indent = self.indent.saturating_sub(1);
+        self.write_indent();
+        self.out.push('}');
+        self.last_end = span.end;
+    }
+
+    /// A `for` initializer, including its `;`.
+    fn clause(&self, id: StmtId) -> String {
+        match &self.ast[id] {
+            Statement::Var(name, None) => format!("var {};", name.lexeme),
+            Statement::Var(name, Some(initializer)) => {
+                format!("var {} = {};", name.lexeme, self.expr(*initializer))
+            }
+            Statement::Expression(expression) => format!("{};", self.expr(*expression)),
+            _ => unreachable!("for initializers are variable or expression statements"),
+        }
+    }
+
+    /// An expression following `prefix` on the current line, broken before
+    /// its binary operators when the line would get too long.
+    fn wrapped(&self, prefix: &str, id: ExprId) -> String {
+        let flat = self.expr(id);
+        let width = self
+            .indent
+            .saturating_mul(INDENT.len())
+            .saturating_add(prefix.len())
+            .saturating_add(flat.chars().count())
+            .saturating_add(1);
+        if width <= MAX_WIDTH {
+            return flat;
+        }
+        let continuation = INDENT.repeat(self.indent.saturating_add(1));
+        self.broken(id, &continuation)
+    }
+
+    fn broken(&self, id: ExprId, continuation: &str) -> String {
+        match &self.ast[id] {
+            Expr::Binary(left, operator, right) => format!(
+                "{}\n{}{} {}",
+                self.broken(*left, continuation),
+                continuation,
+                operator,
+                self.expr(*right)
+            ),
+            Expr::Logical(left, operator, right) => format!(
+                "{}\n{}{} {}",
+                self.broken(*left, continuation),
+                continuation,
+                operator,
+                self.expr(*right)
+            ),
+            _ => self.expr(id),
+        }
+    }
+
+    fn expr(&self, id: ExprId) -> String {
+        match &self.ast[id] {
+            // Literals keep their spelling, e.g. `0xff` or `1.50d`.
+            Expr::Literal(_) => {
+                let span = self.ast.expr_span(id);
+                self.source[span.start..span.end].to_string()
+            }
+            Expr::Unary(operator, operand) => format!("{}{}", operator, self.expr(*operand)),
+            Expr::Binary(left, operator, right) => {
+                format!("{} {} {}", self.expr(*left), operator, self.expr(*right))
+            }
+            Expr::Logical(left, operator, right) => {
+                format!("{} {} {}", self.expr(*left), operator, self.expr(*right))
+            }
+            Expr::Grouping(expression) => format!("({})", self.expr(*expression)),
+            Expr::Variable(name) => name.lexeme.to_string(),
+            Expr::Assign(name, value) => format!("{} = {}", name.lexeme, self.expr(*value)),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use std::mem;
+
+/// Handle to an object owned by a [`Heap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjRef(usize);
+
+#[derive(Debug)]
+pub enum Object {
+    String(String),
+}
+
+impl Object {
+    pub(crate) fn size(&self) -> usize {
+        let payload = match self {
+            Object::String(string) => string.capacity(),
+        };
+        mem::size_of::<Object>().saturating_add(payload)
+    }
+
+    /// Objects directly reachable from this one.
+    fn references(&self) -> Vec<ObjRef> {
+        match self {
+            Object::String(_) => Vec::new(),
+        }
+    }
+}
+
+struct Slot {
+    marked: bool,
+    object: Object,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GcStats {
+    pub collections: usize,
+    pub objects_allocated: usize,
+    pub objects_freed: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+    pub peak_bytes: usize,
+}
+
+impl fmt::Display for GcStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "[gc] collections: {}", self.collections)?;
+        writeln!(
+            f,
+            "[gc] objects allocated: {}, freed: {}",
+            self.objects_allocated, self.objects_freed
+        )?;
+        writeln!(
+            f,
+            "[gc] bytes allocated: {}, freed: {}",
+            self.bytes_allocated, self.bytes_freed
+        )?;
+        write!(f, "[gc] peak heap size: {} bytes", self.peak_bytes)
+    }
+}
+
+const INITIAL_THRESHOLD: usize = 1024 * 1024;
+const GROWTH_FACTOR: usize = 2;
+
+/// Mark-and-sweep heap owning every shared runtime object.
+///
+/// The heap does not know about roots itself: the interpreter decides when
+/// to collect (see [`Heap::should_collect`]), marks everything it can reach
+/// with [`Heap::mark`] and then calls [`Heap::sweep`].
+pub struct Heap {
+    slots: Vec<Option<Slot>>,
+    free: Vec<usize>,
+    bytes: usize,
+    next_gc: usize,
+    gray: Vec<ObjRef>,
+    /// Collect before every allocation, to shake out missing roots.
+    pub stress: bool,
+    pub stats: GcStats,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Heap {
+            slots: Vec::new(),
+            free: Vec::new(),
+            bytes: 0,
+            next_gc: INITIAL_THRESHOLD,
+            gray: Vec::new(),
+            stress: false,
+            stats: GcStats::default(),
+        }
+    }
+}
+
+impl Heap {
+    pub fn should_collect(&self) -> bool {
+        self.stress || self.bytes > self.next_gc
+    }
+
+    pub fn alloc(&mut self, object: Object) -> ObjRef {
+        let size = object.size();
+        self.bytes = self.bytes.saturating_add(size);
+        self.stats.objects_allocated = self.stats.objects_allocated.saturating_add(1);
+        self.stats.bytes_allocated = self.stats.bytes_allocated.saturating_add(size);
+        self.stats.peak_bytes = self.stats.peak_bytes.max(self.bytes);
+
+        let slot = Some(Slot {
+            marked: false,
+            object,
+        });
+        match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = slot;
+                ObjRef(index)
+            }
+            None => {
+                let index = self.slots.len();
+                self.slots.push(slot);
+                ObjRef(index)
+            }
+        }
+    }
+
+    pub fn get(&self, reference: ObjRef) -> &Object {
+        match &self.slots[reference.0] {
+            Some(slot) => &slot.object,
+            None => panic!("Use of collected object {:?}", reference),
+        }
+    }
+
+    /// Number of bytes currently held by live and not yet swept objects.
+    pub fn size(&self) -> usize {
+        self.bytes
+    }
+
+    /// Marks `reference` and everything reachable from it as live.
+    pub fn mark(&mut self, reference: ObjRef) {
+        self.gray.push(reference);
+
+        while let Some(reference) = self.gray.pop() {
+            let Some(slot) = self.slots[reference.0].as_mut() else {
+                continue;
+            };
+            if slot.marked {
+                continue;
+            }
+            slot.marked = true;
+            self.gray.extend(slot.object.references());
+        }
+    }
+
+    /// Frees every object that was not marked since the last sweep.
+    pub fn sweep(&mut self) {
+        for (index, entry) in self.slots.iter_mut().enumerate() {
+            match entry {
+                Some(slot) if slot.marked => slot.marked = false,
+                Some(slot) => {
+                    let size = slot.object.size();
+                    self.bytes = self.bytes.saturating_sub(size);
+                    self.stats.objects_freed = self.stats.objects_freed.saturating_add(1);
+                    self.stats.bytes_freed = self.stats.bytes_freed.saturating_add(size);
+                    *entry = None;
+                    self.free.push(index);
+                }
+                None => {}
+            }
+        }
+
+        self.stats.collections = self.stats.collections.saturating_add(1);
+        self.next_gc = self
+            .bytes
+            .saturating_mul(GROWTH_FACTOR)
+            .max(INITIAL_THRESHOLD);
+    }
+}
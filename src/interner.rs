@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+/// An interned string.
+///
+/// Identifiers and string constants are stored once in a per-thread table and
+/// referred to by index, so comparing or hashing two symbols never touches the
+/// underlying characters. Symbols are only meaningful on the thread that
+/// created them.
+///
+/// The table is never freed, so only text read from source is interned.
+/// Strings built while running a program are heap objects instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+#[derive(Default)]
+struct Interner {
+    map: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+impl Symbol {
+    pub fn intern(string: &str) -> Symbol {
+        INTERNER.with(|interner| {
+            let mut interner = interner.borrow_mut();
+            if let Some(symbol) = interner.map.get(string) {
+                return *symbol;
+            }
+
+            // Interned strings live for the rest of the program, which lets
+            // `as_str` hand out plain `&'static str`s.
+            let string: &'static str = Box::leak(string.to_string().into_boxed_str());
+            let symbol = Symbol(u32::try_from(interner.strings.len()).expect("too many symbols"));
+            interner.strings.push(string);
+            interner.map.insert(string, symbol);
+            symbol
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        INTERNER.with(|interner| interner.borrow().strings[self.0 as usize])
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
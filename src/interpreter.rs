@@ -1,44 +1,140 @@
 use core::fmt;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::mem;
+use std::time::{Duration, Instant};
 
+use crate::ast::{Ast, ExprId, StmtId};
+use crate::bigint::BigInt;
+use crate::decimal::{Decimal, DecimalContext};
 use crate::expr::{BinaryOp, Expr, Literal, LogicalOp, UnaryOp};
+use crate::heap::{Heap, ObjRef, Object};
+use crate::interner::Symbol;
+use crate::span::Span;
 use crate::statement::Statement;
 
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum Error {
+    #[error("{0}")]
     RuntimeError(String),
+    #[error("Tried dividing by zero!")]
     DivisionByZeroError,
+    #[error("Stack overflow.")]
+    StackOverflowError,
+    #[error("Integer overflow.")]
+    IntegerOverflowError,
+    #[error("Execution step limit exceeded.")]
+    StepLimitExceededError,
+    #[error("Execution timed out.")]
+    TimeoutError,
+    #[error("String length limit exceeded.")]
+    StringTooLongError,
+    #[error("Heap size limit exceeded.")]
+    HeapLimitExceededError,
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Error {
+    /// Stable identifier of this kind of error.
+    pub fn code(&self) -> &'static str {
         match self {
-            Error::RuntimeError(message) => write!(f, "{}", message),
-            Error::DivisionByZeroError => write!(f, "Tried dividing by zero!"),
+            Error::RuntimeError(_) => "E0201",
+            Error::DivisionByZeroError => "E0202",
+            Error::StackOverflowError => "E0203",
+            Error::IntegerOverflowError => "E0204",
+            Error::StepLimitExceededError => "E0205",
+            Error::TimeoutError => "E0206",
+            Error::StringTooLongError => "E0207",
+            Error::HeapLimitExceededError => "E0208",
         }
     }
 }
 
-pub struct Environment {
-    pub map: HashMap<String, Value>,
-    pub enclosing: HashMap<String, Value>,
+/// Name of the frame running the top level of a script.
+pub const SCRIPT_FRAME: &str = "<script>";
+
+/// One entry of the call stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Name of the function running in this frame.
+    pub function: String,
+    /// Line being executed: the call site for outer frames, the failing line
+    /// for the innermost one.
+    pub line: usize,
 }
 
-impl Default for Environment {
-    fn default() -> Self {
-        Environment {
-            map: HashMap::new(),
-            enclosing: HashMap::new(),
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, in {}", self.line, self.function)
+    }
+}
+
+/// An [`Error`] together with where in the source it happened.
+#[derive(Debug, thiserror::Error)]
+#[error("Runtime error at line {}", span.line)]
+pub struct RuntimeError {
+    #[source]
+    pub error: Error,
+    /// Span of the innermost expression or statement that failed.
+    pub span: Span,
+    /// The call stack when the error happened, outermost frame first.
+    pub frames: Vec<Frame>,
+}
+
+impl RuntimeError {
+    /// Formats the error as a Python-style traceback, quoting the failing
+    /// line of each frame from `source`.
+    pub fn traceback(&self, file_name: &str, source: &str) -> String {
+        let mut out = String::from("Traceback (most recent call last):\n");
+        for frame in &self.frames {
+            out.push_str(&format!("  File \"{}\", {}\n", file_name, frame));
+            if let Some(line) = source.lines().nth(frame.line.saturating_sub(1)) {
+                out.push_str(&format!("    {}\n", line.trim()));
+            }
         }
+        out.push_str(&format!("RuntimeError: {}\n", self.error));
+        out
     }
 }
 
+/// Default for [`Interpreter::max_depth`]. Like the parser's limit, it is
+/// low enough for the 2 MiB stacks of spawned threads, even in debug builds,
+/// and leaves room for everything the parser accepts by default.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// How many steps run between two checks of the wall-clock deadline. Must be
+/// a power of two.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Results of exact arithmetic from this many limbs on take long enough to
+/// compute that the deadline is checked before each one.
+const LARGE_NUMBER_LIMBS: usize = 1024;
+
+/// Resource limits for running untrusted scripts. `None` means unlimited.
+#[derive(Debug, Clone, Default)]
+pub struct Sandbox {
+    /// Maximum number of statements and expressions evaluated.
+    pub max_steps: Option<u64>,
+    /// Maximum wall-clock time a single `interpret` call may take.
+    pub timeout: Option<Duration>,
+    /// Maximum length in bytes of a string built at runtime.
+    pub max_string_length: Option<usize>,
+    /// Maximum number of bytes held by the heap.
+    pub max_heap_size: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct Environment {
+    pub map: HashMap<Symbol, Value>,
+    pub enclosing: HashMap<Symbol, Value>,
+}
+
 impl Environment {
-    fn define(&mut self, key: String, value: Value) {
+    fn define(&mut self, key: Symbol, value: Value) {
         self.map.insert(key, value);
     }
 
-    fn get(&self, key: &String) -> Result<Value, Error> {
-        match (self.map.get(key), self.enclosing.get(key)) {
+    fn get(&self, key: Symbol) -> Result<Value, Error> {
+        match (self.map.get(&key), self.enclosing.get(&key)) {
             (Some(value), _) => Ok(value.clone()),
             (None, Some(value)) => Ok(value.clone()),
             (None, None) => Err(Error::RuntimeError(format!(
@@ -48,19 +144,39 @@ impl Environment {
         }
     }
 
-    fn assign(&mut self, name: String, value: Value) -> Result<(), Error> {
-        if self.map.contains_key(&name) {
-            self.map.insert(name, value);
-            return Ok(());
+    fn assign(&mut self, name: Symbol, value: Value) -> Result<(), Error> {
+        if let Some(slot) = self.map.get_mut(&name) {
+            *slot = value;
         } else {
             self.enclosing.insert(name, value);
-            return Ok(());
         }
+        return Ok(());
     }
 }
 
 pub struct Interpreter {
     pub environment: Environment,
+    pub heap: Heap,
+    /// Values held by the interpreter itself while it is evaluating, which
+    /// the garbage collector has to treat as roots.
+    temporaries: Vec<Value>,
+    /// Deepest nesting of expressions and statements evaluated before
+    /// failing with [`Error::StackOverflowError`].
+    pub max_depth: usize,
+    /// How many `evaluate`/`execute` calls are currently on the Rust stack.
+    depth: usize,
+    pub sandbox: Sandbox,
+    /// Scale and rounding used when a division of exact numbers does not
+    /// terminate.
+    pub decimal_context: DecimalContext,
+    steps: u64,
+    deadline: Option<Instant>,
+    /// Span of the innermost node that failed, recorded while the error
+    /// propagates out of `evaluate`/`execute`.
+    error_span: Option<Span>,
+    /// Functions currently running, outermost first. A call records its
+    /// call-site line in the caller's frame before pushing the callee's.
+    frames: Vec<Frame>,
 }
 
 impl Default for Interpreter {
@@ -69,190 +185,402 @@ impl Default for Interpreter {
             environment: Environment {
                 ..Default::default()
             },
+            heap: Heap::default(),
+            temporaries: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+            sandbox: Sandbox::default(),
+            decimal_context: DecimalContext::default(),
+            steps: 0,
+            deadline: None,
+            error_span: None,
+            frames: Vec::new(),
         }
     }
 }
 
 impl Interpreter {
-    pub fn interpret(&mut self, statements: Vec<Statement>) -> Result<Vec<Value>, Error> {
-        let mut values = Vec::new();
+    pub fn interpret(
+        &mut self,
+        ast: &Ast,
+        statements: &[StmtId],
+    ) -> Result<Vec<Value>, RuntimeError> {
+        // The values of earlier statements are handed back to the caller, so
+        // they have to survive collections triggered by later statements.
+        let base = self.temporaries.len();
+        self.steps = 0;
+        // A timeout too long to represent never expires.
+        self.deadline = self
+            .sandbox
+            .timeout
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+        self.error_span = None;
+        self.frames = vec![Frame {
+            function: SCRIPT_FRAME.to_string(),
+            line: 0,
+        }];
 
         for statement in statements {
-            values.push(self.execute(statement)?);
+            match self.execute(ast, *statement) {
+                Ok(value) => self.temporaries.push(value),
+                Err(error) => {
+                    self.temporaries.truncate(base);
+                    let span = self
+                        .error_span
+                        .take()
+                        .unwrap_or(ast.statement_span(*statement));
+                    let mut frames = std::mem::take(&mut self.frames);
+                    if let Some(innermost) = frames.last_mut() {
+                        innermost.line = span.line;
+                    }
+                    return Err(RuntimeError {
+                        error,
+                        span,
+                        frames,
+                    });
+                }
+            }
+        }
+        Ok(self.temporaries.split_off(base))
+    }
+
+    /// Renders a value the way `print` shows it.
+    pub fn stringify(&self, value: &Value) -> String {
+        match value {
+            Value::Object(reference) => match self.heap.get(*reference) {
+                Object::String(string) => format!("\"{}\"", string),
+            },
+            _ => value.to_string(),
+        }
+    }
+
+    pub fn collect_garbage(&mut self) {
+        let roots = self
+            .environment
+            .map
+            .values()
+            .chain(self.environment.enclosing.values())
+            .chain(self.temporaries.iter());
+        for value in roots {
+            if let Value::Object(reference) = value {
+                self.heap.mark(*reference);
+            }
+        }
+
+        self.heap.sweep();
+    }
+
+    fn allocate(&mut self, object: Object) -> Result<ObjRef, Error> {
+        let exceeds_limit = |heap: &Heap, limit: &Option<usize>| {
+            limit.is_some_and(|limit| heap.size().saturating_add(object.size()) > limit)
+        };
+
+        if self.heap.should_collect() || exceeds_limit(&self.heap, &self.sandbox.max_heap_size) {
+            self.collect_garbage();
+        }
+        if exceeds_limit(&self.heap, &self.sandbox.max_heap_size) {
+            return Err(Error::HeapLimitExceededError);
+        }
+        Ok(self.heap.alloc(object))
+    }
+
+    fn as_str<'v>(&'v self, value: &'v Value) -> Option<&'v str> {
+        match value {
+            Value::String(symbol) => Some(symbol.as_str()),
+            Value::Object(reference) => match self.heap.get(*reference) {
+                Object::String(string) => Some(string),
+            },
+            _ => None,
+        }
+    }
+
+    fn concatenate(&mut self, left: &Value, right: &Value) -> Result<Value, Error> {
+        let string = match (self.as_str(left), self.as_str(right)) {
+            (Some(left), Some(right)) => {
+                if self
+                    .sandbox
+                    .max_string_length
+                    .is_some_and(|limit| left.len().saturating_add(right.len()) > limit)
+                {
+                    return Err(Error::StringTooLongError);
+                }
+                format!("{}{}", left, right)
+            }
+            _ => {
+                return Err(Error::RuntimeError(
+                    "Operands must be two strings.".to_string(),
+                ))
+            }
+        };
+        Ok(Value::Object(self.allocate(Object::String(string))?))
+    }
+
+    fn is_truthy(&self, value: &Value) -> bool {
+        match value {
+            Value::Object(_) => self.as_str(value).is_some_and(|string| !string.is_empty()),
+            _ => is_truthy(value),
+        }
+    }
+
+    /// Runs `f` one level deeper, failing cleanly instead of overflowing the
+    /// native stack once `max_depth` nested evaluations are reached.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= self.max_depth {
+            return Err(Error::StackOverflowError);
+        }
+        self.step()?;
+
+        self.depth = self.depth.saturating_add(1);
+        let result = f(self);
+        self.depth = self.depth.saturating_sub(1);
+        result
+    }
+
+    /// Counts one unit of work against the sandbox limits.
+    fn step(&mut self) -> Result<(), Error> {
+        self.steps = self.steps.saturating_add(1);
+
+        if self
+            .sandbox
+            .max_steps
+            .is_some_and(|limit| self.steps > limit)
+        {
+            return Err(Error::StepLimitExceededError);
+        }
+        if self.steps & (DEADLINE_CHECK_INTERVAL - 1) == 0 {
+            self.check_deadline()?;
+        }
+        Ok(())
+    }
+
+    fn check_deadline(&self) -> Result<(), Error> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Error::TimeoutError);
+        }
+        Ok(())
+    }
+
+    /// Checks exact arithmetic against the sandbox before computing it.
+    ///
+    /// BigInts and decimals live outside the heap, so its accounting never
+    /// sees them. Instead, a result that could need more bytes than the whole
+    /// heap may hold is refused, and the deadline is checked before a large
+    /// operation rather than only every few steps.
+    fn check_exact_size(
+        &self,
+        left: &Value,
+        operator: BinaryOp,
+        right: &Value,
+    ) -> Result<(), Error> {
+        if !matches!(left, Value::BigInt(_) | Value::Decimal(_))
+            && !matches!(right, Value::BigInt(_) | Value::Decimal(_))
+        {
+            return Ok(());
+        }
+        let (Some(left), Some(right)) = (exact_size(left), exact_size(right)) else {
+            return Ok(());
+        };
+
+        let limbs = exact_result_limbs(left, operator, right, &self.decimal_context);
+        if self
+            .sandbox
+            .max_heap_size
+            .is_some_and(|limit| limbs.saturating_mul(mem::size_of::<u32>()) > limit)
+        {
+            return Err(Error::HeapLimitExceededError);
+        }
+        if limbs >= LARGE_NUMBER_LIMBS {
+            self.check_deadline()?;
         }
-        Ok(values)
+        Ok(())
     }
 
-    fn evaluate(&mut self, expr: &Expr) -> Result<Value, Error> {
-        return match expr {
+    fn evaluate(&mut self, ast: &Ast, expr: ExprId) -> Result<Value, Error> {
+        let result = self.nested(|interpreter| interpreter.evaluate_expr(ast, expr));
+        if result.is_err() && self.error_span.is_none() {
+            self.error_span = Some(ast.expr_span(expr));
+        }
+        result
+    }
+
+    fn evaluate_expr(&mut self, ast: &Ast, expr: ExprId) -> Result<Value, Error> {
+        return match &ast[expr] {
             Expr::Literal(literal) => Ok(expression_literal_to_value(literal)),
-            Expr::Grouping(expr) => self.evaluate(expr),
+            Expr::Grouping(expr) => self.evaluate(ast, *expr),
             Expr::Unary(operator, expression_right) => {
-                self.interpret_unary(*operator, expression_right)
+                self.interpret_unary(ast, *operator, *expression_right)
             }
             Expr::Binary(left_expression, operator, right_expression) => {
-                self.interpret_binary(left_expression, *operator, right_expression)
+                self.interpret_binary(ast, *left_expression, *operator, *right_expression)
             }
-            Expr::Variable(token) => self.environment.get(&token.lexeme),
+            Expr::Variable(token) => self.environment.get(token.lexeme),
             Expr::Assign(token, expression) => {
-                let value = self.evaluate(&expression)?;
-                self.environment
-                    .assign(token.lexeme.clone(), value.clone())?;
+                let value = self.evaluate(ast, *expression)?;
+                self.environment.assign(token.lexeme, value.clone())?;
                 return Ok(value);
             }
-            Expr::Logical(left, operator, right) => self.interpret_logical(left, *operator, right),
+            Expr::Logical(left, operator, right) => {
+                self.interpret_logical(ast, *left, *operator, *right)
+            }
         };
     }
 
     fn interpret_logical(
         &mut self,
-        left: &Expr,
+        ast: &Ast,
+        left: ExprId,
         operator: LogicalOp,
-        right: &Expr,
+        right: ExprId,
     ) -> Result<Value, Error> {
-        let left = self.evaluate(left)?;
+        let left = self.evaluate(ast, left)?;
 
         match operator {
             LogicalOp::Or => {
-                if is_truthy(&left) {
+                if self.is_truthy(&left) {
                     return Ok(left);
                 }
             }
             _ => {
-                if !is_truthy(&left) {
+                if !self.is_truthy(&left) {
                     return Ok(left);
                 }
             }
         }
 
-        return self.evaluate(right);
+        return self.evaluate(ast, right);
     }
 
-    fn interpret_unary<'a>(
+    fn interpret_unary(
         &mut self,
+        ast: &Ast,
         operator: UnaryOp,
-        expression: &Expr,
+        expression: ExprId,
     ) -> Result<Value, Error> {
-        let value = self.evaluate(expression)?;
+        let value = self.evaluate(ast, expression)?;
         match (operator, &value) {
             (UnaryOp::Minus, Value::Number(n)) => Ok(Value::Number(-n)),
+            (UnaryOp::Minus, Value::Int(n)) => n
+                .checked_neg()
+                .map(Value::Int)
+                .ok_or(Error::IntegerOverflowError),
+            // Exact numbers have no fixed width to overflow.
+            #[allow(clippy::arithmetic_side_effects)]
+            (UnaryOp::Minus, Value::BigInt(n)) => Ok(Value::BigInt(-n)),
+            #[allow(clippy::arithmetic_side_effects)]
+            (UnaryOp::Minus, Value::Decimal(n)) => Ok(Value::Decimal(-n)),
             (UnaryOp::Minus, _) => {
                 Err(Error::RuntimeError("Operand must be a number.".to_string()))
             }
-            (UnaryOp::Bang, _) => Ok(Value::Bool(!is_truthy(&value))),
+            (UnaryOp::Bang, _) => Ok(Value::Bool(!self.is_truthy(&value))),
         }
     }
 
-    fn execute(&mut self, statement: Statement) -> Result<Value, Error> {
-        match statement {
+    fn execute(&mut self, ast: &Ast, statement: StmtId) -> Result<Value, Error> {
+        let result = self.nested(|interpreter| interpreter.execute_statement(ast, statement));
+        if result.is_err() && self.error_span.is_none() {
+            self.error_span = Some(ast.statement_span(statement));
+        }
+        result
+    }
+
+    fn execute_statement(&mut self, ast: &Ast, statement: StmtId) -> Result<Value, Error> {
+        match &ast[statement] {
             Statement::Print(expr) => {
-                let value = self.evaluate(&expr)?;
-                println!("{}", value);
+                let value = self.evaluate(ast, *expr)?;
+                println!("{}", self.stringify(&value));
                 Ok(value)
             }
-            Statement::Expression(expr) => self.evaluate(&expr),
+            Statement::Expression(expr) => self.evaluate(ast, *expr),
             Statement::Var(token, expr) => {
-                match expr {
-                    Some(expression) => match self.evaluate(&expression) {
-                        Ok(value) => Some(self.environment.define(token.lexeme, value)),
-                        Err(_) => None,
-                    },
-                    None => None,
-                };
+                if let Some(expression) = expr {
+                    let value = self.evaluate(ast, *expression)?;
+                    self.environment.define(token.lexeme, value);
+                }
 
                 Ok(Value::Nil)
             }
             Statement::Block(statements) => {
                 for statement in statements {
-                    self.execute(statement)?;
+                    self.execute(ast, *statement)?;
                 }
                 Ok(Value::Nil)
             }
             Statement::If(condition, then_branch, else_branch) => {
-                let x = self.evaluate(&condition)?;
+                let x = self.evaluate(ast, *condition)?;
 
-                if is_truthy(&x) {
-                    self.execute(*then_branch)?;
-                } else if else_branch.is_some() {
-                    let y = else_branch.unwrap();
-                    self.execute(*y)?;
+                if self.is_truthy(&x) {
+                    self.execute(ast, *then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(ast, *else_branch)?;
                 }
                 Ok(Value::Nil)
             }
             Statement::While(expr, statement) => {
-                while is_truthy(&self.evaluate(&expr)?) {
-                    self.execute(*statement.clone())?;
+                loop {
+                    let condition = self.evaluate(ast, *expr)?;
+                    if !self.is_truthy(&condition) {
+                        break;
+                    }
+                    self.execute(ast, *statement)?;
                 }
                 Ok(Value::Nil)
             }
         }
     }
 
-    fn interpret_binary<'a>(
+    fn interpret_binary(
         &mut self,
-        left_expression: &Expr,
+        ast: &Ast,
+        left_expression: ExprId,
         operator: BinaryOp,
-        right_expression: &Expr,
+        right_expression: ExprId,
     ) -> Result<Value, Error> {
-        let left = self.evaluate(left_expression)?;
-        let right = self.evaluate(right_expression)?;
-
-        return match (left, operator, right) {
-            (Value::Number(n1), BinaryOp::Minus, Value::Number(n2)) => Ok(Value::Number(n1 - n2)),
-            (_, BinaryOp::Minus, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
-            }
-
-            (Value::Number(n1), BinaryOp::Slash, Value::Number(n2)) => {
-                if n2 == 0.0 {
-                    Err(Error::DivisionByZeroError)
-                } else {
-                    Ok(Value::Number(n1 / n2))
-                }
-            }
-            (_, BinaryOp::Slash, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
-            }
-
-            (Value::Number(n1), BinaryOp::Plus, Value::Number(n2)) => Ok(Value::Number(n1 + n2)),
-            (Value::String(n1), BinaryOp::Plus, Value::String(n2)) => {
-                Ok(Value::String(format!("{}{}", n1, n2)))
-            }
-
-            (Value::Number(n1), BinaryOp::Star, Value::Number(n2)) => Ok(Value::Number(n1 * n2)),
-            (_, BinaryOp::Star, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
-            }
-
-            (Value::Number(n1), BinaryOp::Greater, Value::Number(n2)) => Ok(Value::Bool(n1 > n2)),
-            (_, BinaryOp::Greater, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
-            }
-
-            (Value::Number(n1), BinaryOp::GreaterEqual, Value::Number(n2)) => {
-                Ok(Value::Bool(n1 >= n2))
-            }
-            (_, BinaryOp::GreaterEqual, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
-            }
-
-            (Value::Number(n1), BinaryOp::Less, Value::Number(n2)) => Ok(Value::Bool(n1 < n2)),
-            (_, BinaryOp::Less, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
+        let left = self.evaluate(ast, left_expression)?;
+        // Keep `left` alive while `right` is evaluated, as that may collect.
+        self.temporaries.push(left);
+        let right = self.evaluate(ast, right_expression);
+        let left = self.temporaries.pop().unwrap();
+        let right = right?;
+
+        match (operator, &left, &right) {
+            (
+                BinaryOp::Plus,
+                Value::String(_) | Value::Object(_),
+                Value::String(_) | Value::Object(_),
+            ) => return self.concatenate(&left, &right),
+            (BinaryOp::EqualEqual | BinaryOp::BangEqual, Value::Object(_), _)
+            | (BinaryOp::EqualEqual | BinaryOp::BangEqual, _, Value::Object(_)) => {
+                let equal = self.as_str(&left) == self.as_str(&right);
+                return Ok(Value::Bool(equal == (operator == BinaryOp::EqualEqual)));
             }
+            _ => {}
+        }
 
-            (Value::Number(n1), BinaryOp::LessEqual, Value::Number(n2)) => {
-                Ok(Value::Bool(n1 <= n2))
-            }
-            (_, BinaryOp::LessEqual, _) => {
-                Err(Error::RuntimeError("Operands must be numbers.".to_string()))
-            }
+        self.check_exact_size(&left, operator, &right)?;
+        if let Some(result) = numeric_binary(&left, operator, &right, &self.decimal_context) {
+            return result;
+        }
 
-            (Value::Number(n1), BinaryOp::BangEqual, Value::Number(n2)) => {
-                Ok(Value::Bool(n1 != n2))
+        return match (left, operator, right) {
+            (
+                _,
+                BinaryOp::Minus
+                | BinaryOp::Slash
+                | BinaryOp::Star
+                | BinaryOp::Greater
+                | BinaryOp::GreaterEqual
+                | BinaryOp::Less
+                | BinaryOp::LessEqual,
+                _,
+            ) => Err(Error::RuntimeError("Operands must be numbers.".to_string())),
+            (Value::String(s1), BinaryOp::EqualEqual, Value::String(s2)) => {
+                Ok(Value::Bool(s1 == s2))
             }
-            (Value::Number(n1), BinaryOp::EqualEqual, Value::Number(n2)) => {
-                Ok(Value::Bool(n1 != n2))
+            (Value::String(s1), BinaryOp::BangEqual, Value::String(s2)) => {
+                Ok(Value::Bool(s1 != s2))
             }
             (Value::Nil, BinaryOp::EqualEqual, Value::Nil) => Ok(Value::Bool(true)),
             (Value::Nil, _, _) => Ok(Value::Bool(false)),
@@ -267,38 +595,275 @@ impl Interpreter {
 
 #[derive(Debug, Clone)]
 pub enum Value {
+    Int(i64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Number(f64),
-    String(String),
+    String(Symbol),
     Bool(bool),
     Nil,
+    Object(ObjRef),
 }
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::String(str) => write!(f, "\"{}\"", str),
+            Value::Int(n) => write!(f, "{}", n),
+            Value::BigInt(n) => write!(f, "{}", n),
+            Value::Decimal(n) => write!(f, "{}", n),
             Value::Number(n) => write!(f, "{}", n),
             Value::Bool(bool) => write!(f, "{}", bool),
             Value::Nil => write!(f, "nil"),
+            Value::Object(reference) => write!(f, "<object {:?}>", reference),
         }
     }
 }
 
-fn expression_literal_to_value(literal: &Literal) -> Value {
+pub(crate) fn expression_literal_to_value(literal: &Literal) -> Value {
     match literal {
+        Literal::Int(n) => Value::Int(*n),
+        Literal::BigInt(n) => Value::BigInt(n.clone()),
+        Literal::Decimal(n) => Value::Decimal(n.clone()),
         Literal::Number(n) => Value::Number(*n),
-        Literal::Str(string) => Value::String(string.clone()),
+        Literal::Str(string) => Value::String(*string),
         Literal::False => Value::Bool(false),
         Literal::True => Value::Bool(true),
         Literal::Nil => Value::Nil,
     }
 }
 
-fn is_truthy(value: &Value) -> bool {
+pub(crate) fn is_truthy(value: &Value) -> bool {
     match value {
+        Value::Int(n) => *n > 0,
+        Value::BigInt(n) => n.is_positive(),
+        Value::Decimal(n) => n.is_positive(),
         Value::Number(n) => *n > 0.0,
-        Value::String(str) => !str.is_empty(),
+        Value::String(str) => !str.as_str().is_empty(),
         Value::Nil => false,
         Value::Bool(bool) => *bool,
+        Value::Object(_) => true,
+    }
+}
+
+/// Applies `operator` to two numbers.
+///
+/// Integers stay integers as long as the result is exact, overflow is an
+/// error, and an integer mixed with a float is promoted to a float. Exact
+/// numbers (integers, BigInts and decimals) widen to the more precise of the
+/// two, while mixing a float with a BigInt or decimal is an error because it
+/// would silently lose precision. Returns `None` if either operand is not a
+/// number.
+pub(crate) fn numeric_binary(
+    left: &Value,
+    operator: BinaryOp,
+    right: &Value,
+    context: &DecimalContext,
+) -> Option<Result<Value, Error>> {
+    let result = match (left, right) {
+        (Value::Int(n1), Value::Int(n2)) => int_binary(*n1, operator, *n2),
+        (Value::Int(n1), Value::Number(n2)) => float_binary(*n1 as f64, operator, *n2),
+        (Value::Number(n1), Value::Int(n2)) => float_binary(*n1, operator, *n2 as f64),
+        (Value::Number(n1), Value::Number(n2)) => float_binary(*n1, operator, *n2),
+        (Value::Number(_), Value::BigInt(_) | Value::Decimal(_))
+        | (Value::BigInt(_) | Value::Decimal(_), Value::Number(_)) => Err(Error::RuntimeError(
+            "Cannot implicitly mix floating-point and exact numbers.".to_string(),
+        )),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            decimal_binary(&to_decimal(left)?, operator, &to_decimal(right)?, context)
+        }
+        _ => bigint_binary(&to_bigint(left)?, operator, &to_bigint(right)?, context),
+    };
+    Some(result)
+}
+
+fn to_bigint(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(n) => Some(BigInt::from_i64(*n)),
+        Value::BigInt(n) => Some(n.clone()),
+        _ => None,
+    }
+}
+
+fn to_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Decimal(n) => Some(n.clone()),
+        _ => to_bigint(value).map(Decimal::from_bigint),
+    }
+}
+
+/// The limbs of the coefficient and the scale of an exact number, or `None`
+/// if `value` is not one.
+fn exact_size(value: &Value) -> Option<(usize, u32)> {
+    match value {
+        Value::Int(_) => Some((2, 0)),
+        Value::BigInt(n) => Some((n.limbs(), 0)),
+        Value::Decimal(n) => Some((n.coefficient().limbs(), n.scale())),
+        _ => None,
+    }
+}
+
+/// Upper bound on the limbs of the numbers built while applying `operator`
+/// to exact numbers of the given sizes, following how [`Decimal`] aligns
+/// scales, rounds products and divides.
+fn exact_result_limbs(
+    (limbs1, scale1): (usize, u32),
+    operator: BinaryOp,
+    (limbs2, scale2): (usize, u32),
+    context: &DecimalContext,
+) -> usize {
+    // Multiplying by 10^digits; a limb holds more than 9 decimal digits.
+    let shifted = |limbs: usize, digits: u64| {
+        if digits == 0 {
+            return limbs;
+        }
+        limbs.saturating_add(usize::try_from((digits / 9).saturating_add(1)).unwrap_or(usize::MAX))
+    };
+    // Sums and comparisons of decimals first align them to the larger scale.
+    let scale = scale1.max(scale2);
+    let aligned = shifted(limbs1, u64::from(scale.saturating_sub(scale1)))
+        .max(shifted(limbs2, u64::from(scale.saturating_sub(scale2))));
+
+    match operator {
+        BinaryOp::Plus | BinaryOp::Minus => aligned.saturating_add(1),
+        BinaryOp::Star => {
+            // Digits beyond the scale limit are rounded off with a division
+            // by a power of ten.
+            let limit = context.scale.max(scale);
+            let excess = u64::from(scale1)
+                .saturating_add(u64::from(scale2))
+                .saturating_sub(u64::from(limit));
+            limbs1.saturating_add(limbs2).max(shifted(0, excess))
+        }
+        BinaryOp::Slash => {
+            // Also covers a BigInt quotient that turns out to be inexact.
+            // Three 32-bit scales cannot overflow 64 bits.
+            #[allow(clippy::arithmetic_side_effects)]
+            let shift = i64::from(context.scale) + i64::from(scale2) - i64::from(scale1);
+            let shifted = if shift >= 0 {
+                shifted(limbs1, shift.unsigned_abs())
+            } else {
+                shifted(limbs2, shift.unsigned_abs())
+            };
+            shifted.max(limbs1).max(limbs2)
+        }
+        _ if scale1 != scale2 => aligned,
+        // Comparing at the same scale builds nothing.
+        _ => 0,
+    }
+}
+
+fn bigint_binary(
+    n1: &BigInt,
+    operator: BinaryOp,
+    n2: &BigInt,
+    context: &DecimalContext,
+) -> Result<Value, Error> {
+    match operator {
+        // `check_exact_size` has already bounded the result.
+        #[allow(clippy::arithmetic_side_effects)]
+        BinaryOp::Plus => Ok(Value::BigInt(n1 + n2)),
+        #[allow(clippy::arithmetic_side_effects)]
+        BinaryOp::Minus => Ok(Value::BigInt(n1 - n2)),
+        #[allow(clippy::arithmetic_side_effects)]
+        BinaryOp::Star => Ok(Value::BigInt(n1 * n2)),
+        BinaryOp::Slash => match n1.div_rem(n2) {
+            None => Err(Error::DivisionByZeroError),
+            Some((quotient, remainder)) if remainder.is_zero() => Ok(Value::BigInt(quotient)),
+            // An inexact quotient would lose precision as a float.
+            Some(_) => decimal_binary(
+                &Decimal::from_bigint(n1.clone()),
+                operator,
+                &Decimal::from_bigint(n2.clone()),
+                context,
+            ),
+        },
+        _ => Ok(compare(n1.cmp(n2), operator)),
+    }
+}
+
+fn decimal_binary(
+    n1: &Decimal,
+    operator: BinaryOp,
+    n2: &Decimal,
+    context: &DecimalContext,
+) -> Result<Value, Error> {
+    match operator {
+        // `check_exact_size` has already bounded the result.
+        #[allow(clippy::arithmetic_side_effects)]
+        BinaryOp::Plus => Ok(Value::Decimal(n1 + n2)),
+        #[allow(clippy::arithmetic_side_effects)]
+        BinaryOp::Minus => Ok(Value::Decimal(n1 - n2)),
+        BinaryOp::Star => n1
+            .multiply(n2, context)
+            .map(Value::Decimal)
+            .ok_or_else(|| Error::RuntimeError("Decimal scale overflow.".to_string())),
+        BinaryOp::Slash => n1
+            .divide(n2, context)
+            .map(Value::Decimal)
+            .ok_or(Error::DivisionByZeroError),
+        _ => Ok(compare(n1.cmp(n2), operator)),
+    }
+}
+
+/// Evaluates a comparison `operator` given how its operands are ordered.
+fn compare(ordering: Ordering, operator: BinaryOp) -> Value {
+    Value::Bool(match operator {
+        BinaryOp::EqualEqual => ordering == Ordering::Equal,
+        BinaryOp::BangEqual => ordering != Ordering::Equal,
+        BinaryOp::Less => ordering == Ordering::Less,
+        BinaryOp::LessEqual => ordering != Ordering::Greater,
+        BinaryOp::Greater => ordering == Ordering::Greater,
+        BinaryOp::GreaterEqual => ordering != Ordering::Less,
+        BinaryOp::Plus | BinaryOp::Minus | BinaryOp::Star | BinaryOp::Slash => {
+            unreachable!("{:?} is not a comparison", operator)
+        }
+    })
+}
+
+fn int_binary(n1: i64, operator: BinaryOp, n2: i64) -> Result<Value, Error> {
+    let checked = |result: Option<i64>| result.map(Value::Int).ok_or(Error::IntegerOverflowError);
+
+    match operator {
+        BinaryOp::Plus => checked(n1.checked_add(n2)),
+        BinaryOp::Minus => checked(n1.checked_sub(n2)),
+        BinaryOp::Star => checked(n1.checked_mul(n2)),
+        BinaryOp::Slash => {
+            if n2 == 0 {
+                return Err(Error::DivisionByZeroError);
+            }
+            match n1.checked_rem(n2) {
+                Some(0) => checked(n1.checked_div(n2)),
+                Some(_) => Ok(Value::Number(n1 as f64 / n2 as f64)),
+                None => Err(Error::IntegerOverflowError),
+            }
+        }
+        BinaryOp::EqualEqual => Ok(Value::Bool(n1 == n2)),
+        BinaryOp::BangEqual => Ok(Value::Bool(n1 != n2)),
+        BinaryOp::Less => Ok(Value::Bool(n1 < n2)),
+        BinaryOp::LessEqual => Ok(Value::Bool(n1 <= n2)),
+        BinaryOp::Greater => Ok(Value::Bool(n1 > n2)),
+        BinaryOp::GreaterEqual => Ok(Value::Bool(n1 >= n2)),
+    }
+}
+
+fn float_binary(n1: f64, operator: BinaryOp, n2: f64) -> Result<Value, Error> {
+    match operator {
+        BinaryOp::Plus => Ok(Value::Number(n1 + n2)),
+        BinaryOp::Minus => Ok(Value::Number(n1 - n2)),
+        BinaryOp::Star => Ok(Value::Number(n1 * n2)),
+        BinaryOp::Slash => {
+            if n2 == 0.0 {
+                Err(Error::DivisionByZeroError)
+            } else {
+                Ok(Value::Number(n1 / n2))
+            }
+        }
+        BinaryOp::EqualEqual => Ok(Value::Bool(n1 == n2)),
+        BinaryOp::BangEqual => Ok(Value::Bool(n1 != n2)),
+        BinaryOp::Less => Ok(Value::Bool(n1 < n2)),
+        BinaryOp::LessEqual => Ok(Value::Bool(n1 <= n2)),
+        BinaryOp::Greater => Ok(Value::Bool(n1 > n2)),
+        BinaryOp::GreaterEqual => Ok(Value::Bool(n1 >= n2)),
     }
 }
@@ -0,0 +1,512 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ast::{Ast, ExprId, StmtId};
+use crate::diagnostic::Diagnostic;
+use crate::expr::{self, Expr};
+use crate::span::Span;
+use crate::statement::Statement;
+use crate::token::token::{self, Token};
+
+/// A JSON value, as written by `--format=json` and exchanged with editors by
+/// `loxc lsp`.
+///
+/// Object keys keep their insertion order so the output is stable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// The value of `key` if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(value: i64) -> Json {
+        Json::Int(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Json {
+        Json::Int(value as i64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Json {
+        Json::String(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Json {
+        Json::Array(values)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Int(value) => write!(f, "{}", value),
+            // JSON has no infinities or NaN.
+            Json::Float(value) if !value.is_finite() => write!(f, "null"),
+            Json::Float(value) if value.fract() == 0.0 => write!(f, "{:.1}", value),
+            Json::Float(value) => write!(f, "{}", value),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Nesting deeper than this is rejected rather than risking a stack overflow.
+const MAX_DEPTH: usize = 128;
+
+impl FromStr for Json {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = JsonParser {
+            source: s,
+            current: 0,
+        };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        if parser.current < s.len() {
+            return Err(parser.error("Unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+}
+
+struct JsonParser<'a> {
+    source: &'a str,
+    /// Byte offset of the next character.
+    current: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.current)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.current..].chars().next()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.current = self.current.saturating_add(c.len_utf8());
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.current = self.current.saturating_add(1);
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.advance() != Some(expected) {
+            return Err(self.error(&format!("Expected `{}`", expected)));
+        }
+        Ok(())
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        if !self.source[self.current..].starts_with(keyword) {
+            return Err(self.error("Unexpected character"));
+        }
+        self.current = self.current.saturating_add(keyword.len());
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("Nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => {
+                self.advance();
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.advance();
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value(depth.saturating_add(1))?);
+                    self.skip_whitespace();
+                    match self.advance() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Json::Array(values)),
+                        _ => return Err(self.error("Expected `,` or `]`")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.advance();
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.advance();
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some('"') {
+                        return Err(self.error("Expected a string key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    fields.push((key, self.value(depth.saturating_add(1))?));
+                    self.skip_whitespace();
+                    match self.advance() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Json::Object(fields)),
+                        _ => return Err(self.error("Expected `,` or `}`")),
+                    }
+                }
+            }
+            Some('-' | '0'..='9') => self.number(),
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.current;
+        let mut is_float = false;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' | '-' | '+' => {}
+                '.' | 'e' | 'E' => is_float = true,
+                _ => break,
+            }
+            self.current = self.current.saturating_add(1);
+        }
+        let text = &self.source[start..self.current];
+        let value = if is_float {
+            text.parse().ok().map(Json::Float)
+        } else {
+            text.parse()
+                .ok()
+                .map(Json::Int)
+                .or_else(|| text.parse().ok().map(Json::Float))
+        };
+        value.ok_or_else(|| self.error("Invalid number"))
+    }
+
+    /// A string literal, starting at its opening quote.
+    fn string(&mut self) -> Result<String, String> {
+        self.advance();
+        let mut value = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let c = match self.advance() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("Invalid escape")),
+                    };
+                    value.push(c);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("Control character in string"))
+                }
+                Some(c) => value.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+    }
+
+    /// The character of a `\uXXXX` escape, or of a surrogate pair of them.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("Invalid escape"));
+        }
+        if !self.source[self.current..].starts_with("\\u") {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        self.current = self.current.saturating_add(2);
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("Unpaired surrogate"));
+        }
+        // Both halves were checked to be in their surrogate ranges.
+        #[allow(clippy::arithmetic_side_effects)]
+        let code = 0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00);
+        char::from_u32(code).ok_or_else(|| self.error("Invalid escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .source
+            .get(self.current..)
+            .and_then(|rest| rest.get(..4))
+            .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("Invalid escape"))?;
+        self.current = self.current.saturating_add(4);
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+pub(crate) fn span(span: Span) -> Json {
+    Json::object(vec![
+        ("start", span.start.into()),
+        ("end", span.end.into()),
+        ("line", span.line.into()),
+        ("column", span.column.into()),
+    ])
+}
+
+/// `{"code": ..., "message": ..., "span": ...}` for an error.
+pub(crate) fn diagnostic(diagnostic: &Diagnostic) -> Json {
+    Json::object(vec![
+        ("code", diagnostic.code.into()),
+        ("message", diagnostic.message.as_str().into()),
+        ("span", span(diagnostic.span)),
+    ])
+}
+
+fn typed(kind: &'static str, value: Json) -> Json {
+    Json::object(vec![("type", kind.into()), ("value", value)])
+}
+
+pub(crate) fn token(token: &Token) -> Json {
+    let literal = token.literal.as_ref().map(|literal| match literal {
+        token::Literal::Identifier(name) => typed("identifier", name.to_string().into()),
+        token::Literal::Str(string) => typed("string", string.to_string().into()),
+        token::Literal::Integer(n) => typed("integer", Json::Int(*n)),
+        token::Literal::BigInt(n) => typed("bigint", n.to_string().into()),
+        token::Literal::Decimal(n) => typed("decimal", n.to_string().into()),
+        token::Literal::Number(n) => typed("number", Json::Float(*n)),
+    });
+    Json::object(vec![
+        ("type", token.token_type.to_string().into()),
+        ("lexeme", token.lexeme.to_string().into()),
+        ("literal", literal.into()),
+        ("span", span(token.span)),
+    ])
+}
+
+fn literal(literal: &expr::Literal) -> Json {
+    match literal {
+        expr::Literal::Str(string) => typed("string", string.to_string().into()),
+        expr::Literal::Int(n) => typed("integer", Json::Int(*n)),
+        expr::Literal::BigInt(n) => typed("bigint", n.to_string().into()),
+        expr::Literal::Decimal(n) => typed("decimal", n.to_string().into()),
+        expr::Literal::Number(n) => typed("number", Json::Float(*n)),
+        expr::Literal::Nil => typed("nil", Json::Null),
+        expr::Literal::False => typed("boolean", false.into()),
+        expr::Literal::True => typed("boolean", true.into()),
+    }
+}
+
+/// `{"kind": ..., <children>..., "span": ...}` for an expression.
+pub(crate) fn expr(ast: &Ast, id: ExprId) -> Json {
+    let mut fields: Vec<(&'static str, Json)> = match &ast[id] {
+        Expr::Literal(value) => vec![("kind", "Literal".into()), ("value", literal(value))],
+        Expr::Unary(operator, operand) => vec![
+            ("kind", "Unary".into()),
+            ("operator", operator.to_string().into()),
+            ("operand", expr(ast, *operand)),
+        ],
+        Expr::Binary(left, operator, right) => vec![
+            ("kind", "Binary".into()),
+            ("operator", operator.to_string().into()),
+            ("left", expr(ast, *left)),
+            ("right", expr(ast, *right)),
+        ],
+        Expr::Logical(left, operator, right) => vec![
+            ("kind", "Logical".into()),
+            ("operator", operator.to_string().into()),
+            ("left", expr(ast, *left)),
+            ("right", expr(ast, *right)),
+        ],
+        Expr::Grouping(expression) => vec![
+            ("kind", "Grouping".into()),
+            ("expression", expr(ast, *expression)),
+        ],
+        Expr::Variable(name) => vec![
+            ("kind", "Variable".into()),
+            ("name", name.lexeme.to_string().into()),
+        ],
+        Expr::Assign(name, value) => vec![
+            ("kind", "Assign".into()),
+            ("name", name.lexeme.to_string().into()),
+            ("value", expr(ast, *value)),
+        ],
+    };
+    fields.push(("span", span(ast.expr_span(id))));
+    Json::object(fields)
+}
+
+/// `{"kind": ..., <children>..., "span": ...}` for a statement.
+pub(crate) fn statement(ast: &Ast, id: StmtId) -> Json {
+    let mut fields: Vec<(&'static str, Json)> = match &ast[id] {
+        Statement::Print(expression) => vec![
+            ("kind", "Print".into()),
+            ("expression", expr(ast, *expression)),
+        ],
+        Statement::Expression(expression) => vec![
+            ("kind", "Expression".into()),
+            ("expression", expr(ast, *expression)),
+        ],
+        Statement::Var(name, initializer) => vec![
+            ("kind", "Var".into()),
+            ("name", name.lexeme.to_string().into()),
+            (
+                "initializer",
+                initializer.map(|initializer| expr(ast, initializer)).into(),
+            ),
+        ],
+        Statement::Block(statements) => vec![
+            ("kind", "Block".into()),
+            ("statements", statement_list(ast, statements)),
+        ],
+        Statement::If(condition, then_branch, else_branch) => vec![
+            ("kind", "If".into()),
+            ("condition", expr(ast, *condition)),
+            ("then_branch", statement(ast, *then_branch)),
+            (
+                "else_branch",
+                else_branch.map(|branch| statement(ast, branch)).into(),
+            ),
+        ],
+        Statement::While(condition, body) => vec![
+            ("kind", "While".into()),
+            ("condition", expr(ast, *condition)),
+            ("body", statement(ast, *body)),
+        ],
+    };
+    fields.push(("span", span(ast.statement_span(id))));
+    Json::object(fields)
+}
+
+pub(crate) fn statement_list(ast: &Ast, statements: &[StmtId]) -> Json {
+    Json::Array(statements.iter().map(|id| statement(ast, *id)).collect())
+}
@@ -1,77 +1,497 @@
 #![allow(clippy::needless_return)]
-#![warn(clippy::integer_arithmetic)]
+#![warn(clippy::arithmetic_side_effects)]
 
-use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
 
-use interpreter::Environment;
+pub use crate::interpreter::{Frame, Sandbox};
 
+use crate::decimal::DecimalContext;
+use crate::diagnostic::{ColorChoice, Diagnostic};
 use crate::interpreter::Interpreter;
+use crate::json::Json;
+use crate::line_editor::LineEditor;
+use crate::linter::{LintConfig, Rule};
+use crate::parse_error::ParseError;
 use crate::parser::Parser;
+use crate::repl::Repl;
+use crate::span::Span;
+use crate::tokenize_error::TokenizeError;
 use crate::tokenizer::Tokenizer;
 
+mod ast;
+pub mod bigint;
+pub mod cst;
+pub mod decimal;
+pub mod diagnostic;
 mod expr;
+pub mod formatter;
+pub mod heap;
+pub mod interner;
 pub mod interpreter;
-mod parse_error;
+pub mod json;
+pub mod line_editor;
+pub mod linter;
+pub mod lsp;
+mod optimizer;
+pub mod parse_error;
 mod parser;
+pub mod repl;
+pub mod span;
 mod statement;
 pub mod token;
+pub mod tokenize_error;
 pub mod tokenizer;
+mod unicode;
 
-#[derive(Debug, PartialEq)]
+/// Exit status for input that does not tokenize or parse (`EX_DATAERR`).
+pub const EXIT_DATA_ERROR: u8 = 65;
+/// Exit status for a script that fails at runtime (`EX_SOFTWARE`).
+pub const EXIT_RUNTIME_ERROR: u8 = 70;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
 pub enum Error {
-    TokenizingError(String),
-    ParseError(String),
-    InterpreterError(String),
+    /// Every error found while scanning, in source order.
+    #[error("{}", join_lines(.0))]
+    TokenizingError(Vec<TokenizeError>),
+    /// Every syntax error found while parsing, in source order.
+    #[error("{}", join_lines(.0))]
+    ParseError(Vec<ParseError>),
+    /// A runtime error, with the call stack at the point of failure,
+    /// outermost frame first.
+    #[error("Runtime error at line {}: {error}", span.line)]
+    InterpreterError {
+        #[source]
+        error: interpreter::Error,
+        span: Span,
+        frames: Vec<Frame>,
+    },
+    /// The script ran more steps than [`Sandbox::max_steps`] allows.
+    #[error("Execution step limit exceeded at line {}.", span.line)]
+    StepLimitExceeded { span: Span },
+    /// The script ran longer than [`Sandbox::timeout`].
+    #[error("Execution timed out at line {}.", span.line)]
+    Timeout { span: Span },
+    /// The script built a string longer than [`Sandbox::max_string_length`].
+    #[error("String length limit exceeded at line {}.", span.line)]
+    StringTooLong { span: Span },
+    /// The script needed more memory than [`Sandbox::max_heap_size`].
+    #[error("Heap size limit exceeded at line {}.", span.line)]
+    HeapLimitExceeded { span: Span },
+}
+
+impl Error {
+    /// The process exit status the CLI uses for this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::TokenizingError(_) | Error::ParseError(_) => EXIT_DATA_ERROR,
+            _ => EXIT_RUNTIME_ERROR,
+        }
+    }
+
+    /// Stable identifier of the (first) error, e.g. `E0101`.
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            Error::TokenizingError(errors) => errors.first().map(TokenizeError::code),
+            Error::ParseError(errors) => errors.first().map(ParseError::code),
+            Error::InterpreterError { error, .. } => Some(error.code()),
+            Error::StepLimitExceeded { .. } => {
+                Some(interpreter::Error::StepLimitExceededError.code())
+            }
+            Error::Timeout { .. } => Some(interpreter::Error::TimeoutError.code()),
+            Error::StringTooLong { .. } => Some(interpreter::Error::StringTooLongError.code()),
+            Error::HeapLimitExceeded { .. } => {
+                Some(interpreter::Error::HeapLimitExceededError.code())
+            }
+        }
+    }
+
+    /// Where the (first) error happened, if it is tied to the source.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::TokenizingError(errors) => errors.first().map(TokenizeError::span),
+            Error::ParseError(errors) => errors.first().map(ParseError::span),
+            Error::InterpreterError { span, .. }
+            | Error::StepLimitExceeded { span }
+            | Error::Timeout { span }
+            | Error::StringTooLong { span }
+            | Error::HeapLimitExceeded { span } => Some(*span),
+        }
+    }
+}
+
+fn join_lines<T: fmt::Display>(errors: &[T]) -> String {
+    let lines: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+    lines.join("\n")
+}
+
+/// Knobs for [`execute_with_options`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Deepest statement/expression nesting the parser accepts.
+    pub max_parse_depth: usize,
+    /// Deepest statement/expression nesting the interpreter evaluates.
+    pub max_eval_depth: usize,
+    /// Resource limits for untrusted scripts.
+    pub sandbox: Sandbox,
+    /// Scale and rounding of inexact BigInt and decimal division.
+    pub decimal_context: DecimalContext,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_parse_depth: parser::DEFAULT_MAX_DEPTH,
+            max_eval_depth: interpreter::DEFAULT_MAX_DEPTH,
+            sandbox: Sandbox::default(),
+            decimal_context: DecimalContext::default(),
+        }
+    }
 }
 
 pub fn execute(source: &str) -> Result<Vec<String>, Error> {
+    execute_with_options(source, &Options::default())
+}
+
+pub fn execute_with_options(source: &str, options: &Options) -> Result<Vec<String>, Error> {
     let mut tokenizer = Tokenizer {
         source,
         ..Default::default()
     };
 
-    let results = tokenizer.scan_tokens();
-    if results != 0 {
-        return Err(Error::TokenizingError("Failed parsing tokens".to_string()));
-    }
+    tokenizer.scan_tokens().map_err(Error::TokenizingError)?;
 
     let mut parser = Parser {
         tokens: tokenizer.tokens,
+        max_depth: options.max_parse_depth,
         ..Default::default()
     };
 
-    let statements = match parser.parse() {
-        Ok(statements) => statements,
-        Err(err) => {
-            return Err(Error::ParseError(err.to_string()));
-        }
-    };
+    let statements = parser.parse().map_err(Error::ParseError)?;
 
-    let mut interpreter = Interpreter {
-        environment: Environment {
-            map: HashMap::new(),
-            enclosing: HashMap::new(),
+    let mut interpreter = Interpreter::default();
+    interpreter.max_depth = options.max_eval_depth;
+    interpreter.sandbox = options.sandbox.clone();
+    interpreter.decimal_context = options.decimal_context;
+
+    match interpreter.interpret(&parser.ast, &statements) {
+        Ok(values) => Ok(values
+            .iter()
+            .map(|value| interpreter.stringify(value))
+            .collect()),
+        Err(err) => match err.error {
+            interpreter::Error::StepLimitExceededError => {
+                Err(Error::StepLimitExceeded { span: err.span })
+            }
+            interpreter::Error::TimeoutError => Err(Error::Timeout { span: err.span }),
+            interpreter::Error::StringTooLongError => Err(Error::StringTooLong { span: err.span }),
+            interpreter::Error::HeapLimitExceededError => {
+                Err(Error::HeapLimitExceeded { span: err.span })
+            }
+            error => Err(Error::InterpreterError {
+                error,
+                span: err.span,
+                frames: err.frames,
+            }),
         },
-    };
+    }
+}
+
+/// How `tokenize` and `parse` print their results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Format {
+    /// One token or S-expression per line.
+    #[default]
+    Text,
+    /// A single JSON object, for other tools to consume.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("Unknown format: {}", s)),
+        }
+    }
+}
+
+/// Flags and file name following the subcommand on the command line.
+#[derive(Default)]
+struct CommandLine {
+    /// Files to work on. Only `fmt` takes more than one.
+    files: Vec<String>,
+    options: Options,
+    optimize: bool,
+    gc_stress: bool,
+    gc_stats: bool,
+    color: ColorChoice,
+    format: Format,
+    /// `fmt --check`: report unformatted files instead of rewriting them.
+    check: bool,
+    /// Rules `lint` runs, changed by `--enable` and `--disable`.
+    lints: LintConfig,
+}
+
+impl CommandLine {
+    fn parse(args: &[String]) -> Result<CommandLine, String> {
+        let mut cli = CommandLine::default();
+
+        for arg in args {
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value)),
+                None => (arg.as_str(), None),
+            };
+
+            match (flag, value) {
+                ("-O", None) => cli.optimize = true,
+                ("--gc-stress", None) => cli.gc_stress = true,
+                ("--gc-stats", None) => cli.gc_stats = true,
+                ("--check", None) => cli.check = true,
+                ("--max-depth", Some(value)) => {
+                    let depth = parse_flag_value(flag, value)?;
+                    cli.options.max_parse_depth = depth;
+                    cli.options.max_eval_depth = depth;
+                }
+                ("--max-steps", Some(value)) => {
+                    cli.options.sandbox.max_steps = Some(parse_flag_value(flag, value)?);
+                }
+                ("--timeout-ms", Some(value)) => {
+                    let millis = parse_flag_value(flag, value)?;
+                    cli.options.sandbox.timeout = Some(Duration::from_millis(millis));
+                }
+                ("--max-string-length", Some(value)) => {
+                    cli.options.sandbox.max_string_length = Some(parse_flag_value(flag, value)?);
+                }
+                ("--max-heap-size", Some(value)) => {
+                    cli.options.sandbox.max_heap_size = Some(parse_flag_value(flag, value)?);
+                }
+                ("--color", Some(value)) => cli.color = parse_flag_value(flag, value)?,
+                ("--format", Some(value)) => cli.format = parse_flag_value(flag, value)?,
+                ("--enable" | "--disable", Some(value)) => {
+                    for name in value.split(',') {
+                        let rules = match name {
+                            "all" => Rule::ALL.to_vec(),
+                            _ => vec![parse_flag_value(flag, name)?],
+                        };
+                        for rule in rules {
+                            if flag == "--enable" {
+                                cli.lints.enable(rule);
+                            } else {
+                                cli.lints.disable(rule);
+                            }
+                        }
+                    }
+                }
+                ("--decimal-scale", Some(value)) => {
+                    cli.options.decimal_context.scale = parse_flag_value(flag, value)?;
+                }
+                ("--rounding", Some(value)) => {
+                    cli.options.decimal_context.rounding = parse_flag_value(flag, value)?;
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+                _ => cli.files.push(arg.clone()),
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+fn parse_flag_value<T: FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+/// Prints `diagnostic` to stderr with a snippet of `source`.
+fn report(cli: &CommandLine, filename: &str, source: &str, diagnostic: &Diagnostic) {
+    eprint!(
+        "{}",
+        diagnostic.render(filename, source, cli.color.enabled())
+    );
+}
+
+fn json_errors(diagnostics: &[Diagnostic]) -> Json {
+    Json::Array(diagnostics.iter().map(json::diagnostic).collect())
+}
+
+/// Runs `loxc fmt`: rewrites each file in the canonical style, or with
+/// `--check` only lists the files that would change.
+fn format_files(cli: &CommandLine) -> ExitCode {
+    let mut status = ExitCode::SUCCESS;
+    for filename in &cli.files {
+        let source = match fs::read_to_string(filename) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("Failed to read file {}: {}", filename, err);
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+
+        let formatted = match formatter::format(&source) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                let diagnostics: Vec<Diagnostic> = match &err {
+                    Error::TokenizingError(errors) => {
+                        errors.iter().map(TokenizeError::to_diagnostic).collect()
+                    }
+                    Error::ParseError(errors) => {
+                        errors.iter().map(ParseError::to_diagnostic).collect()
+                    }
+                    _ => Vec::new(),
+                };
+                for diagnostic in &diagnostics {
+                    report(cli, filename, &source, diagnostic);
+                }
+                status = ExitCode::from(err.exit_code());
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
 
-    match interpreter.interpret(statements) {
-        Ok(values) => Ok(values.into_iter().map(|value| value.to_string()).collect()),
-        Err(err) => return Err(Error::InterpreterError(err.to_string())),
+        if cli.check {
+            eprintln!("Would reformat {}", filename);
+            if status == ExitCode::SUCCESS {
+                status = ExitCode::FAILURE;
+            }
+        } else if let Err(err) = fs::write(filename, formatted) {
+            eprintln!("Failed to write file {}: {}", filename, err);
+            status = ExitCode::FAILURE;
+        }
     }
+    status
 }
 
-fn main() -> ExitCode {
+/// Runs `loxc lint`: prints a warning for every lint found in the files.
+/// Fails if there were any.
+fn lint_files(cli: &CommandLine) -> ExitCode {
+    let mut status = ExitCode::SUCCESS;
+    for filename in &cli.files {
+        let source = match fs::read_to_string(filename) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("Failed to read file {}: {}", filename, err);
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+
+        let diagnostics: Vec<Diagnostic> = match linter::lint(&source, &cli.lints) {
+            Ok(lints) => {
+                if !lints.is_empty() && status == ExitCode::SUCCESS {
+                    status = ExitCode::FAILURE;
+                }
+                lints.iter().map(linter::Lint::to_diagnostic).collect()
+            }
+            Err(err) => {
+                status = ExitCode::from(err.exit_code());
+                match &err {
+                    Error::TokenizingError(errors) => {
+                        errors.iter().map(TokenizeError::to_diagnostic).collect()
+                    }
+                    Error::ParseError(errors) => {
+                        errors.iter().map(ParseError::to_diagnostic).collect()
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        };
+        for diagnostic in &diagnostics {
+            report(cli, filename, &source, diagnostic);
+        }
+    }
+    status
+}
+
+pub fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
+    if args.len() < 2 {
         eprintln!("Usage: {} tokenize <filename>", args[0]);
+        eprintln!("       {} repl", args[0]);
+        eprintln!("       {} lsp", args[0]);
+        eprintln!("       {} fmt [--check] <filename>...", args[0]);
+        eprintln!(
+            "       {} lint [--enable=<rule>,...] [--disable=<rule>,...] <filename>...",
+            args[0]
+        );
         return ExitCode::FAILURE;
     }
 
     let command = &args[1];
-    let filename = &args[2];
+    let cli = match CommandLine::parse(&args[2..]) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if command == "repl" {
+        let mut repl = Repl::new(cli.options);
+        repl.color = cli.color;
+        let result = if io::stdin().is_terminal() {
+            let mut editor = match repl::history_path() {
+                Some(path) => LineEditor::with_history_file(path).unwrap_or_default(),
+                None => LineEditor::default(),
+            };
+            repl.run_interactive(&mut editor)
+        } else {
+            repl.run(io::stdin().lock(), &mut io::stdout(), &mut io::stderr())
+        };
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if command == "lsp" {
+        return match lsp::run(io::stdin().lock(), io::stdout().lock()) {
+            Ok(true) => ExitCode::SUCCESS,
+            // The client exited without asking the server to shut down.
+            Ok(false) => ExitCode::FAILURE,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if command == "fmt" {
+        if cli.files.is_empty() {
+            eprintln!("Usage: {} fmt [--check] <filename>...", args[0]);
+            return ExitCode::FAILURE;
+        }
+        return format_files(&cli);
+    }
+
+    if command == "lint" {
+        if cli.files.is_empty() {
+            eprintln!(
+                "Usage: {} lint [--enable=<rule>,...] [--disable=<rule>,...] <filename>...",
+                args[0]
+            );
+            return ExitCode::FAILURE;
+        }
+        return lint_files(&cli);
+    }
+
+    let Some(filename) = cli.files.first() else {
+        eprintln!("Usage: {} {} <filename>", args[0], command);
+        return ExitCode::FAILURE;
+    };
 
     let source = fs::read_to_string(filename).unwrap_or_else(|_| {
         eprintln!("Failed to read file {}", filename);
@@ -85,12 +505,36 @@ fn main() -> ExitCode {
                 ..Default::default()
             };
 
-            let results = tokenizer.scan_tokens();
-            for token in tokenizer.tokens {
-                println!("{}", token);
+            let result = tokenizer.scan_tokens();
+            let diagnostics: Vec<Diagnostic> = match &result {
+                Ok(()) => Vec::new(),
+                Err(errors) => errors.iter().map(TokenizeError::to_diagnostic).collect(),
+            };
+            for diagnostic in &diagnostics {
+                report(&cli, filename, &source, diagnostic);
+            }
+            match cli.format {
+                Format::Text => {
+                    for token in tokenizer.tokens {
+                        println!("{}", token);
+                    }
+                }
+                Format::Json => {
+                    let tokens = tokenizer.tokens.iter().map(json::token).collect();
+                    println!(
+                        "{}",
+                        Json::object(vec![
+                            ("tokens", Json::Array(tokens)),
+                            ("errors", json_errors(&diagnostics)),
+                        ])
+                    );
+                }
             }
 
-            return ExitCode::from(results);
+            if result.is_err() {
+                return ExitCode::from(EXIT_DATA_ERROR);
+            }
+            return ExitCode::SUCCESS;
         }
         "parse" => {
             let mut tokenizer = Tokenizer {
@@ -98,29 +542,63 @@ fn main() -> ExitCode {
                 ..Default::default()
             };
 
-            let results = tokenizer.scan_tokens();
-            if results != 0 {
-                return ExitCode::from(results);
+            let print_errors = |diagnostics: &[Diagnostic]| {
+                for diagnostic in diagnostics {
+                    report(&cli, filename, &source, diagnostic);
+                }
+                if cli.format == Format::Json {
+                    println!(
+                        "{}",
+                        Json::object(vec![
+                            ("statements", Json::Array(Vec::new())),
+                            ("errors", json_errors(diagnostics)),
+                        ])
+                    );
+                }
+            };
+
+            if let Err(errors) = tokenizer.scan_tokens() {
+                let diagnostics: Vec<Diagnostic> =
+                    errors.iter().map(TokenizeError::to_diagnostic).collect();
+                print_errors(&diagnostics);
+                return ExitCode::from(EXIT_DATA_ERROR);
             }
 
             let mut parser = Parser {
                 tokens: tokenizer.tokens,
+                max_depth: cli.options.max_parse_depth,
                 ..Default::default()
             };
 
-            let statements = match parser.parse() {
+            let mut statements = match parser.parse() {
                 Ok(statements) => statements,
-                Err(err) => {
-                    eprintln!("{}", err);
-                    return ExitCode::from(65);
+                Err(errors) => {
+                    let diagnostics: Vec<Diagnostic> =
+                        errors.iter().map(ParseError::to_diagnostic).collect();
+                    print_errors(&diagnostics);
+                    return ExitCode::from(EXIT_DATA_ERROR);
                 }
             };
+            if cli.optimize {
+                statements = optimizer::optimize(&mut parser.ast, &statements);
+            }
 
-            for statement in statements {
-                println!("{}", statement);
+            match cli.format {
+                Format::Text => {
+                    for statement in statements {
+                        println!("{}", parser.ast.statement_to_string(statement));
+                    }
+                }
+                Format::Json => println!(
+                    "{}",
+                    Json::object(vec![
+                        ("statements", json::statement_list(&parser.ast, &statements)),
+                        ("errors", Json::Array(Vec::new())),
+                    ])
+                ),
             }
 
-            return ExitCode::from(results);
+            return ExitCode::SUCCESS;
         }
         "interpret" => {
             let mut tokenizer = Tokenizer {
@@ -128,40 +606,48 @@ fn main() -> ExitCode {
                 ..Default::default()
             };
 
-            let results = tokenizer.scan_tokens();
-            if results != 0 {
-                return ExitCode::from(results);
+            if let Err(errors) = tokenizer.scan_tokens() {
+                for err in errors {
+                    report(&cli, filename, &source, &err.to_diagnostic());
+                }
+                return ExitCode::from(EXIT_DATA_ERROR);
             }
 
             let mut parser = Parser {
                 tokens: tokenizer.tokens,
+                max_depth: cli.options.max_parse_depth,
                 ..Default::default()
             };
 
-            let statements = match parser.parse() {
+            let mut statements = match parser.parse() {
                 Ok(statements) => statements,
-                Err(err) => {
-                    eprintln!("{}", err);
-                    return ExitCode::from(65);
+                Err(errors) => {
+                    for err in errors {
+                        report(&cli, filename, &source, &err.to_diagnostic());
+                    }
+                    return ExitCode::from(EXIT_DATA_ERROR);
                 }
             };
+            if cli.optimize {
+                statements = optimizer::optimize(&mut parser.ast, &statements);
+            }
 
-            let mut interpreter = Interpreter {
-                environment: Environment {
-                    map: HashMap::new(),
-                    enclosing: HashMap::new(),
-                },
-            };
+            let mut interpreter = Interpreter::default();
+            interpreter.max_depth = cli.options.max_eval_depth;
+            interpreter.sandbox = cli.options.sandbox;
+            interpreter.decimal_context = cli.options.decimal_context;
+            interpreter.heap.stress = cli.gc_stress;
 
-            match interpreter.interpret(statements) {
-                Ok(value) => value,
-                Err(err) => {
-                    eprintln!("{}", err);
-                    return ExitCode::from(70);
-                }
-            };
+            let result = interpreter.interpret(&parser.ast, &statements);
+            if cli.gc_stats {
+                eprintln!("{}", interpreter.heap.stats);
+            }
+            if let Err(err) = result {
+                eprint!("{}", err.traceback(filename, &source));
+                return ExitCode::from(EXIT_RUNTIME_ERROR);
+            }
 
-            return ExitCode::from(results);
+            return ExitCode::SUCCESS;
         }
         _ => {
             eprintln!("Unknown command: {}", command);
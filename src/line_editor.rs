@@ -0,0 +1,458 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// A key press, decoded from the bytes a terminal sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Tab,
+    /// Ctrl-C.
+    Interrupt,
+    /// Ctrl-D.
+    EndOfFile,
+    /// Ctrl-R.
+    ReverseSearch,
+    /// Ctrl-G.
+    Abort,
+    /// Ctrl-K.
+    KillToEnd,
+    /// Ctrl-U.
+    KillToStart,
+    /// Anything the editor does not handle.
+    Unknown,
+}
+
+/// Reads one key from `input`, or `None` once it is exhausted.
+pub fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Interrupt,
+        0x04 => Key::EndOfFile,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x07 => Key::Abort,
+        0x0b => Key::KillToEnd,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x12 => Key::ReverseSearch,
+        0x15 => Key::KillToStart,
+        0x1b => read_escape_sequence(input)?,
+        byte if byte < 0x20 => Key::Unknown,
+        byte => {
+            // Collect the continuation bytes of a UTF-8 sequence.
+            let length = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..length {
+                match read_byte(input)? {
+                    Some(byte) => bytes.push(byte),
+                    None => break,
+                }
+            }
+            match std::str::from_utf8(&bytes) {
+                Ok(text) => Key::Char(text.chars().next().unwrap()),
+                Err(_) => Key::Unknown,
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Decodes the rest of an `ESC [ ...` or `ESC O ...` sequence.
+fn read_escape_sequence(input: &mut impl Read) -> io::Result<Key> {
+    let introducer = read_byte(input)?;
+    if !matches!(introducer, Some(b'[') | Some(b'O')) {
+        return Ok(Key::Unknown);
+    }
+
+    let mut parameter = String::new();
+    while let Some(byte) = read_byte(input)? {
+        let key = match byte {
+            b'0'..=b'9' | b';' => {
+                parameter.push(byte as char);
+                continue;
+            }
+            b'A' => Key::Up,
+            b'B' => Key::Down,
+            b'C' => Key::Right,
+            b'D' => Key::Left,
+            b'H' => Key::Home,
+            b'F' => Key::End,
+            b'~' => match parameter.as_str() {
+                "1" | "7" => Key::Home,
+                "3" => Key::Delete,
+                "4" | "8" => Key::End,
+                _ => Key::Unknown,
+            },
+            _ => Key::Unknown,
+        };
+        return Ok(key);
+    }
+    Ok(Key::Unknown)
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// What [`LineEditor::read_line`] got from the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    Line(String),
+    /// Ctrl-C: the line was thrown away.
+    Interrupted,
+    /// Ctrl-D on an empty line, or the input ended.
+    Eof,
+}
+
+/// An in-progress Ctrl-R search through the history.
+struct Search {
+    query: String,
+    /// History index of the entry currently shown.
+    found: Option<usize>,
+}
+
+/// The line being edited.
+struct State {
+    line: Vec<char>,
+    cursor: usize,
+    /// History entry shown, `history.len()` for the line being typed.
+    history_index: usize,
+    /// What was typed before moving into the history.
+    draft: Vec<char>,
+    search: Option<Search>,
+}
+
+impl State {
+    fn set_line(&mut self, line: &str) {
+        self.line = line.chars().collect();
+        self.cursor = self.line.len();
+    }
+
+    fn insert(&mut self, text: &str) {
+        for c in text.chars() {
+            self.line.insert(self.cursor, c);
+            self.cursor = self.cursor.saturating_add(1);
+        }
+    }
+
+    fn text(&self) -> String {
+        self.line.iter().collect()
+    }
+}
+
+/// Reads lines from a terminal with Emacs-style editing keys, a history
+/// browsable with the arrow keys and Ctrl-R, and Tab completion.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    /// Entered lines, oldest first.
+    pub history: Vec<String>,
+    /// Where new history entries are appended, if anywhere.
+    pub history_file: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// Loads the history from `path`, which new lines are then appended to.
+    /// A missing file is treated as an empty history.
+    pub fn with_history_file(path: PathBuf) -> io::Result<LineEditor> {
+        let history = match fs::read_to_string(&path) {
+            Ok(contents) => contents.lines().map(String::from).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(LineEditor {
+            history,
+            history_file: Some(path),
+        })
+    }
+
+    /// Records an entered line, skipping blank lines and repeats of the
+    /// previous entry.
+    pub fn add_history(&mut self, line: &str) -> io::Result<()> {
+        if line.trim().is_empty() || self.history.last().map(String::as_str) == Some(line) {
+            return Ok(());
+        }
+        self.history.push(line.to_string());
+
+        if let Some(path) = &self.history_file {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Reads one line, echoing edits to `out`. `completions` are the words
+    /// offered for Tab.
+    pub fn read_line(
+        &self,
+        prompt: &str,
+        completions: &[String],
+        input: &mut impl Read,
+        out: &mut impl Write,
+    ) -> io::Result<Input> {
+        let mut state = State {
+            line: Vec::new(),
+            cursor: 0,
+            history_index: self.history.len(),
+            draft: Vec::new(),
+            search: None,
+        };
+        draw(out, prompt, &state, &self.history)?;
+
+        loop {
+            let Some(key) = read_key(input)? else {
+                writeln!(out)?;
+                if state.line.is_empty() {
+                    return Ok(Input::Eof);
+                }
+                return Ok(Input::Line(state.text()));
+            };
+
+            if state.search.is_some() && self.search_key(&mut state, key) {
+                draw(out, prompt, &state, &self.history)?;
+                continue;
+            }
+
+            match key {
+                Key::Char(c) => state.insert(c.encode_utf8(&mut [0; 4])),
+                Key::Enter => {
+                    writeln!(out)?;
+                    return Ok(Input::Line(state.text()));
+                }
+                Key::Backspace => {
+                    if let Some(cursor) = state.cursor.checked_sub(1) {
+                        state.cursor = cursor;
+                        state.line.remove(cursor);
+                    }
+                }
+                Key::Delete => {
+                    if state.cursor < state.line.len() {
+                        state.line.remove(state.cursor);
+                    }
+                }
+                Key::EndOfFile => {
+                    if state.line.is_empty() {
+                        writeln!(out)?;
+                        return Ok(Input::Eof);
+                    }
+                    if state.cursor < state.line.len() {
+                        state.line.remove(state.cursor);
+                    }
+                }
+                Key::Interrupt => {
+                    writeln!(out, "^C")?;
+                    return Ok(Input::Interrupted);
+                }
+                Key::Left => state.cursor = state.cursor.saturating_sub(1),
+                Key::Right => state.cursor = state.cursor.saturating_add(1).min(state.line.len()),
+                Key::Home => state.cursor = 0,
+                Key::End => state.cursor = state.line.len(),
+                Key::KillToEnd => state.line.truncate(state.cursor),
+                Key::KillToStart => {
+                    state.line.drain(..state.cursor);
+                    state.cursor = 0;
+                }
+                Key::Up => self.move_in_history(&mut state, -1),
+                Key::Down => self.move_in_history(&mut state, 1),
+                Key::Tab => complete(out, &mut state, completions)?,
+                Key::ReverseSearch => {
+                    state.search = Some(Search {
+                        query: String::new(),
+                        found: None,
+                    });
+                }
+                Key::Abort | Key::Unknown => {}
+            }
+            draw(out, prompt, &state, &self.history)?;
+        }
+    }
+
+    fn move_in_history(&self, state: &mut State, step: isize) {
+        let Some(index) = state.history_index.checked_add_signed(step) else {
+            return;
+        };
+        if index > self.history.len() {
+            return;
+        }
+
+        if state.history_index == self.history.len() {
+            state.draft = state.line.clone();
+        }
+        state.history_index = index;
+        if index == self.history.len() {
+            state.line = state.draft.clone();
+            state.cursor = state.line.len();
+        } else {
+            state.set_line(&self.history[index]);
+        }
+    }
+
+    /// Handles a key while searching. Returns `false` when the search ended
+    /// and the key still has to be handled as a normal edit.
+    fn search_key(&self, state: &mut State, key: Key) -> bool {
+        let search = state.search.as_mut().unwrap();
+        match key {
+            Key::Char(c) => {
+                search.query.push(c);
+                search.found = self.search_before(&search.query, self.history.len());
+            }
+            Key::Backspace => {
+                search.query.pop();
+                search.found = self.search_before(&search.query, self.history.len());
+            }
+            Key::ReverseSearch => {
+                let before = search.found.unwrap_or(self.history.len());
+                if let Some(found) = self.search_before(&search.query, before) {
+                    search.found = Some(found);
+                }
+            }
+            Key::Abort | Key::Interrupt => state.search = None,
+            _ => {
+                // Any other key keeps the match and goes back to editing.
+                if let Some(found) = search.found {
+                    state.history_index = found;
+                    state.set_line(&self.history[found]);
+                }
+                state.search = None;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Newest history entry older than `before` containing `query`.
+    fn search_before(&self, query: &str, before: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+        self.history[..before]
+            .iter()
+            .rposition(|entry| entry.contains(query))
+    }
+}
+
+/// Completes the word before the cursor. With several candidates, their
+/// common prefix is inserted, or they are listed when there is none.
+fn complete(out: &mut impl Write, state: &mut State, completions: &[String]) -> io::Result<()> {
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let start = state.line[..state.cursor]
+        .iter()
+        .rposition(|c| !is_word(c))
+        .map_or(0, |position| position.saturating_add(1));
+    let prefix: String = state.line[start..state.cursor].iter().collect();
+    if prefix.is_empty() {
+        return Ok(());
+    }
+
+    let matches: Vec<&String> = completions
+        .iter()
+        .filter(|candidate| candidate.starts_with(&prefix))
+        .collect();
+    let Some(first) = matches.first() else {
+        return write!(out, "\x07");
+    };
+
+    let mut common = first.as_str();
+    for candidate in &matches[1..] {
+        let length = common
+            .char_indices()
+            .zip(candidate.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(common.len().min(candidate.len()), |((index, _), _)| index);
+        common = &common[..length];
+    }
+
+    if common.len() > prefix.len() {
+        state.insert(&common[prefix.len()..]);
+    } else if matches.len() > 1 {
+        let names: Vec<&str> = matches.iter().map(|name| name.as_str()).collect();
+        write!(out, "\n{}\n", names.join("  "))?;
+    }
+    Ok(())
+}
+
+fn draw(out: &mut impl Write, prompt: &str, state: &State, history: &[String]) -> io::Result<()> {
+    match &state.search {
+        Some(search) => {
+            let found = search.found.map_or("", |index| history[index].as_str());
+            write!(
+                out,
+                "\r\x1b[K(reverse-i-search)`{}': {}",
+                search.query, found
+            )?;
+        }
+        None => {
+            write!(out, "\r\x1b[K{}{}", prompt, state.text())?;
+            let back = state.line.len().saturating_sub(state.cursor);
+            if back > 0 {
+                write!(out, "\x1b[{}D", back)?;
+            }
+        }
+    }
+    out.flush()
+}
+
+/// Switches the terminal on stdin to reading key by key without echo for as
+/// long as it is alive.
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?.trim().to_string();
+        stty(&[
+            "-icanon", "-echo", "-isig", "-ixon", "min", "1", "time", "0",
+        ])?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(message.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
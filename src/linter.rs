@@ -0,0 +1,442 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ast::{Ast, ExprId, StmtId};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::expr::{BinaryOp, Expr};
+use crate::interner::Symbol;
+use crate::parser::Parser;
+use crate::span::Span;
+use crate::statement::Statement;
+use crate::token::token::Token;
+use crate::tokenizer::Tokenizer;
+use crate::Error;
+
+/// Comment that silences lints, e.g. `// lox-ignore` or
+/// `// lox-ignore: unused-variable, shadowed-name`.
+const IGNORE_COMMENT: &str = "lox-ignore";
+
+/// A check `loxc lint` can run.
+///
+/// Lox has no functions yet, so there is no `return` to check for code
+/// unreachable after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A variable that is never read or assigned.
+    UnusedVariable,
+    /// A variable that is assigned but never read.
+    UnreadVariable,
+    /// A declaration hiding a variable of the same name.
+    ShadowedName,
+    /// An `if` or loop condition without any variables in it.
+    ConstantCondition,
+    /// `a = a`.
+    SelfAssignment,
+    /// `a == a`, `a < a` and so on.
+    SelfComparison,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 6] = [
+        Rule::UnusedVariable,
+        Rule::UnreadVariable,
+        Rule::ShadowedName,
+        Rule::ConstantCondition,
+        Rule::SelfAssignment,
+        Rule::SelfComparison,
+    ];
+
+    /// The name used on the command line and in `// lox-ignore` comments.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::UnreadVariable => "unread-variable",
+            Rule::ShadowedName => "shadowed-name",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::SelfAssignment => "self-assignment",
+            Rule::SelfComparison => "self-comparison",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.name() == s)
+            .ok_or_else(|| format!("Unknown lint rule: {}", s))
+    }
+}
+
+/// Which rules `loxc lint` runs. All of them by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    pub enabled: Vec<Rule>,
+}
+
+impl Default for LintConfig {
+    fn default() -> LintConfig {
+        LintConfig {
+            enabled: Rule::ALL.to_vec(),
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn enable(&mut self, rule: Rule) {
+        if !self.is_enabled(rule) {
+            self.enabled.push(rule);
+        }
+    }
+
+    pub fn disable(&mut self, rule: Rule) {
+        self.enabled.retain(|enabled| *enabled != rule);
+    }
+
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.enabled.contains(&rule)
+    }
+}
+
+/// A likely mistake found by [`lint`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub message: String,
+    pub span: Span,
+    pub help: Option<String>,
+}
+
+impl Lint {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            code: Some(self.rule.name()),
+            message: self.message.clone(),
+            span: self.span,
+            help: self.help.iter().cloned().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Checks `source` with the rules enabled in `config`, leaving out lints
+/// silenced by a `// lox-ignore` comment. Such a comment applies to its own
+/// line, or to the next line when it is on a line of its own, and can be
+/// limited to some rules: `// lox-ignore: unused-variable`.
+///
+/// Lints are returned in source order. Source that does not tokenize or
+/// parse is an error.
+pub fn lint(source: &str, config: &LintConfig) -> Result<Vec<Lint>, Error> {
+    let mut tokenizer = Tokenizer {
+        source,
+        ..Default::default()
+    };
+    tokenizer.scan_tokens().map_err(Error::TokenizingError)?;
+    let comments = std::mem::take(&mut tokenizer.comments);
+
+    let mut parser = Parser {
+        tokens: tokenizer.tokens,
+        ..Default::default()
+    };
+    let statements = parser.parse().map_err(Error::ParseError)?;
+
+    let mut linter = Linter {
+        source,
+        ast: &parser.ast,
+        scopes: vec![Vec::new()],
+        lints: Vec::new(),
+    };
+    for statement in statements {
+        linter.statement(statement);
+    }
+    linter.end_scope();
+
+    let ignores = ignore_comments(source, &comments);
+    let mut lints = linter.lints;
+    lints.retain(|lint| {
+        config.is_enabled(lint.rule)
+            && !ignores.iter().any(|(line, rules)| {
+                *line == lint.span.line
+                    && match rules {
+                        Some(rules) => rules.contains(&lint.rule),
+                        None => true,
+                    }
+            })
+    });
+    lints.sort_by_key(|lint| lint.span.start);
+    Ok(lints)
+}
+
+/// The lines silenced by `// lox-ignore` comments, with the rules they
+/// silence, or `None` for all of them.
+fn ignore_comments(source: &str, comments: &[Span]) -> Vec<(usize, Option<Vec<Rule>>)> {
+    let mut ignores = Vec::new();
+    for comment in comments {
+        // Skips the `//`.
+        let text = source[comment.start..comment.end][2..].trim();
+        let Some(rest) = text.strip_prefix(IGNORE_COMMENT) else {
+            continue;
+        };
+        let rules = match rest.trim().strip_prefix(':') {
+            Some(names) => Some(
+                names
+                    .split(',')
+                    .filter_map(|name| name.trim().parse().ok())
+                    .collect(),
+            ),
+            None if rest.trim().is_empty() => None,
+            // Something like `// lox-ignored`.
+            None => continue,
+        };
+
+        let line_start = source[..comment.start]
+            .rfind('\n')
+            .map_or(0, |i| i.saturating_add(1));
+        let own_line = source[line_start..comment.start].trim().is_empty();
+        let line = if own_line {
+            comment.line.saturating_add(1)
+        } else {
+            comment.line
+        };
+        ignores.push((line, rules));
+    }
+    ignores
+}
+
+/// A declared variable and how it is used.
+struct Variable {
+    name: Symbol,
+    span: Span,
+    reads: usize,
+    writes: usize,
+}
+
+struct Linter<'a> {
+    source: &'a str,
+    ast: &'a Ast,
+    /// Variables in scope, innermost scope last. The first scope holds the
+    /// globals.
+    scopes: Vec<Vec<Variable>>,
+    lints: Vec<Lint>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: Rule, message: String, span: Span) {
+        self.lints.push(Lint {
+            rule,
+            message,
+            span,
+            help: None,
+        });
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(shadowed) = self.resolve(name.lexeme) {
+            let line = shadowed.span.line;
+            self.lints.push(Lint {
+                rule: Rule::ShadowedName,
+                message: format!("`{}` shadows an earlier declaration.", name.lexeme),
+                span: name.span,
+                help: Some(format!(
+                    "`{}` was first declared on line {}",
+                    name.lexeme, line
+                )),
+            });
+        }
+        self.scopes.last_mut().unwrap().push(Variable {
+            name: name.lexeme,
+            span: name.span,
+            reads: 0,
+            writes: 0,
+        });
+    }
+
+    fn resolve(&mut self, name: Symbol) -> Option<&mut Variable> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|variable| variable.name == name)
+    }
+
+    /// Reports the variables of the innermost scope that were never read.
+    fn end_scope(&mut self) {
+        for variable in self.scopes.pop().unwrap_or_default() {
+            if variable.reads > 0 {
+                continue;
+            }
+            let (rule, message) = if variable.writes == 0 {
+                (
+                    Rule::UnusedVariable,
+                    format!("Unused variable `{}`.", variable.name),
+                )
+            } else {
+                (
+                    Rule::UnreadVariable,
+                    format!("`{}` is assigned but never read.", variable.name),
+                )
+            };
+            self.report(rule, message, variable.span);
+        }
+    }
+
+    fn statement(&mut self, id: StmtId) {
+        let ast = self.ast;
+        if let Some(for_loop) = ast.for_loop(id).copied() {
+            self.scopes.push(Vec::new());
+            if let Some(initializer) = for_loop.initializer {
+                self.statement(initializer);
+            }
+            if let Some(condition) = for_loop.condition {
+                self.condition(condition);
+            }
+            self.statement(for_loop.body);
+            if let Some(increment) = for_loop.increment {
+                self.expr(increment);
+            }
+            self.end_scope();
+            return;
+        }
+
+        match &ast[id] {
+            Statement::Print(expression) | Statement::Expression(expression) => {
+                self.expr(*expression);
+            }
+            Statement::Var(name, initializer) => {
+                if let Some(initializer) = initializer {
+                    self.expr(*initializer);
+                }
+                self.declare(name);
+            }
+            Statement::Block(statements) => {
+                self.scopes.push(Vec::new());
+                for statement in statements {
+                    self.statement(*statement);
+                }
+                self.end_scope();
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.condition(*condition);
+                self.statement(*then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(*else_branch);
+                }
+            }
+            Statement::While(condition, body) => {
+                self.condition(*condition);
+                self.statement(*body);
+            }
+        }
+    }
+
+    fn condition(&mut self, id: ExprId) {
+        if self.is_constant(id) {
+            self.report(
+                Rule::ConstantCondition,
+                String::from("This condition is constant."),
+                self.ast.expr_span(id),
+            );
+        }
+        self.expr(id);
+    }
+
+    fn expr(&mut self, id: ExprId) {
+        let ast = self.ast;
+        match &ast[id] {
+            Expr::Literal(_) => {}
+            Expr::Unary(_, operand) => self.expr(*operand),
+            Expr::Binary(left, operator, right) => {
+                if is_comparison(*operator) && self.is_same(*left, *right) {
+                    self.report(
+                        Rule::SelfComparison,
+                        format!("`{}` is compared to itself.", self.text(*left)),
+                        ast.expr_span(id),
+                    );
+                }
+                self.expr(*left);
+                self.expr(*right);
+            }
+            Expr::Logical(left, _, right) => {
+                self.expr(*left);
+                self.expr(*right);
+            }
+            Expr::Grouping(expression) => self.expr(*expression),
+            Expr::Variable(name) => {
+                if let Some(variable) = self.resolve(name.lexeme) {
+                    variable.reads = variable.reads.saturating_add(1);
+                }
+            }
+            Expr::Assign(name, value) => {
+                if let Expr::Variable(other) = &ast[*value] {
+                    if other.lexeme == name.lexeme {
+                        self.report(
+                            Rule::SelfAssignment,
+                            format!("`{}` is assigned to itself.", name.lexeme),
+                            ast.expr_span(id),
+                        );
+                    }
+                }
+                self.expr(*value);
+                if let Some(variable) = self.resolve(name.lexeme) {
+                    variable.writes = variable.writes.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    /// Whether the expression always has the same value.
+    fn is_constant(&self, id: ExprId) -> bool {
+        match &self.ast[id] {
+            Expr::Literal(_) => true,
+            Expr::Unary(_, operand) | Expr::Grouping(operand) => self.is_constant(*operand),
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.is_constant(*left) && self.is_constant(*right)
+            }
+            Expr::Variable(_) | Expr::Assign(..) => false,
+        }
+    }
+
+    /// Whether two expressions are written the same way and evaluating them
+    /// has no side effects.
+    fn is_same(&self, a: ExprId, b: ExprId) -> bool {
+        match (&self.ast[a], &self.ast[b]) {
+            (Expr::Literal(_), Expr::Literal(_)) => self.text(a) == self.text(b),
+            (Expr::Variable(a), Expr::Variable(b)) => a.lexeme == b.lexeme,
+            (Expr::Grouping(a), Expr::Grouping(b)) => self.is_same(*a, *b),
+            (Expr::Unary(op_a, a), Expr::Unary(op_b, b)) => op_a == op_b && self.is_same(*a, *b),
+            (Expr::Binary(left_a, op_a, right_a), Expr::Binary(left_b, op_b, right_b)) => {
+                op_a == op_b && self.is_same(*left_a, *left_b) && self.is_same(*right_a, *right_b)
+            }
+            (Expr::Logical(left_a, op_a, right_a), Expr::Logical(left_b, op_b, right_b)) => {
+                op_a == op_b && self.is_same(*left_a, *left_b) && self.is_same(*right_a, *right_b)
+            }
+            _ => false,
+        }
+    }
+
+    fn text(&self, id: ExprId) -> &'a str {
+        let span = self.ast.expr_span(id);
+        &self.source[span.start..span.end]
+    }
+}
+
+fn is_comparison(operator: BinaryOp) -> bool {
+    matches!(
+        operator,
+        BinaryOp::EqualEqual
+            | BinaryOp::BangEqual
+            | BinaryOp::Less
+            | BinaryOp::LessEqual
+            | BinaryOp::Greater
+            | BinaryOp::GreaterEqual
+    )
+}
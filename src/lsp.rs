@@ -0,0 +1,717 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+
+use crate::ast::{Ast, ExprId, StmtId};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::expr::Expr;
+use crate::interner::Symbol;
+use crate::json::Json;
+use crate::parse_error::ParseError;
+use crate::parser::Parser;
+use crate::span::Span;
+use crate::statement::Statement;
+use crate::token::token::Token;
+use crate::token::token_type::TokenType;
+use crate::tokenize_error::TokenizeError;
+use crate::tokenizer::Tokenizer;
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Semantic token types, in the order of the legend sent to the client.
+const TOKEN_TYPES: [&str; 6] = [
+    "keyword", "variable", "string", "number", "operator", "comment",
+];
+const KEYWORD: usize = 0;
+const VARIABLE: usize = 1;
+const STRING: usize = 2;
+const NUMBER: usize = 3;
+const OPERATOR: usize = 4;
+const COMMENT: usize = 5;
+/// Bit of the `declaration` semantic token modifier.
+const DECLARATION: usize = 1;
+
+/// `SymbolKind.Variable` in the protocol.
+const SYMBOL_KIND_VARIABLE: i64 = 13;
+
+/// Reads one message framed by a `Content-Length` header. Returns `None` at
+/// the end of the input.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                let value = value.trim().parse();
+                length = Some(value.map_err(|_| invalid("Invalid Content-Length header"))?);
+            }
+        }
+    }
+
+    let length: u64 = length.ok_or_else(|| invalid("Missing Content-Length header"))?;
+    // Read through `take` rather than into a buffer of `length` bytes, which
+    // would trust the header with the size of the allocation.
+    let mut body = Vec::new();
+    input.take(length).read_to_end(&mut body)?;
+    if body.len() as u64 != length {
+        return Err(invalid("Message is shorter than its Content-Length"));
+    }
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| invalid("Message is not UTF-8"))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// Serves `loxc lsp`: answers the messages read from `input` until an `exit`
+/// notification or the end of the input. Returns whether the client asked
+/// the server to shut down first, as it should.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+    let mut server = Server::default();
+    while let Some(message) = read_message(&mut input)? {
+        for reply in server.handle_text(&message) {
+            write_message(&mut output, &reply)?;
+        }
+        if server.exited {
+            break;
+        }
+    }
+    Ok(server.shutdown)
+}
+
+/// An error response to a request.
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    fn invalid_params(message: &str) -> ResponseError {
+        ResponseError {
+            code: INVALID_PARAMS,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// The state of a language server: the documents open in the editor.
+///
+/// It only turns messages into replies, so it can be driven by [`run`] over
+/// stdio or directly by a test.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    /// Whether a `shutdown` request was received.
+    pub shutdown: bool,
+    /// Whether an `exit` notification was received.
+    pub exited: bool,
+}
+
+impl Server {
+    /// Handles a message given as JSON text.
+    pub fn handle_text(&mut self, text: &str) -> Vec<Json> {
+        match text.parse::<Json>() {
+            Ok(message) => self.handle(&message),
+            Err(message) => vec![error_response(
+                Json::Null,
+                ResponseError {
+                    code: PARSE_ERROR,
+                    message,
+                },
+            )],
+        }
+    }
+
+    /// Handles a message, returning the response to it if it is a request
+    /// and any notifications to send to the client.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let Some(method) = message.get("method").and_then(Json::as_str) else {
+            if message.get("method").is_none()
+                && (message.get("result").is_some() || message.get("error").is_some())
+            {
+                // A response from the client. The server never sends requests.
+                return Vec::new();
+            }
+            let id = match message.get("id") {
+                Some(id @ (Json::Int(_) | Json::String(_))) => id.clone(),
+                _ => Json::Null,
+            };
+            return vec![error_response(
+                id,
+                ResponseError {
+                    code: INVALID_REQUEST,
+                    message: String::from("Not a request or notification."),
+                },
+            )];
+        };
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let Some(id) = message.get("id").cloned() else {
+            return self.notification(method, params);
+        };
+
+        let result = if self.shutdown {
+            Err(ResponseError {
+                code: INVALID_REQUEST,
+                message: String::from("The server is shutting down."),
+            })
+        } else {
+            self.request(method, params)
+        };
+        let response = match result {
+            Ok(result) => Json::object(vec![
+                ("jsonrpc", "2.0".into()),
+                ("id", id),
+                ("result", result),
+            ]),
+            Err(error) => error_response(id, error),
+        };
+        vec![response]
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, ResponseError> {
+        match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => {
+                let (uri, document, offset) = self.document_position(params)?;
+                Ok(document
+                    .variable_at(offset)
+                    .map(|(_, variable)| document.location(uri, variable.name_span))
+                    .into())
+            }
+            "textDocument/references" => {
+                let (uri, document, offset) = self.document_position(params)?;
+                let include_declaration = params
+                    .get("context")
+                    .and_then(|context| context.get("includeDeclaration"))
+                    .and_then(Json::as_bool)
+                    .unwrap_or(false);
+                let Some((_, variable)) = document.variable_at(offset) else {
+                    return Ok(Json::Array(Vec::new()));
+                };
+                let mut spans = Vec::new();
+                if include_declaration {
+                    spans.push(variable.name_span);
+                }
+                spans.extend(&variable.references);
+                Ok(spans
+                    .into_iter()
+                    .map(|span| document.location(uri, span))
+                    .collect::<Vec<Json>>()
+                    .into())
+            }
+            "textDocument/hover" => {
+                let (_, document, offset) = self.document_position(params)?;
+                let Some((span, variable)) = document.variable_at(offset) else {
+                    return Ok(Json::Null);
+                };
+                let declaration =
+                    &document.text[variable.declaration.start..variable.declaration.end];
+                Ok(Json::object(vec![
+                    (
+                        "contents",
+                        Json::object(vec![
+                            ("kind", "markdown".into()),
+                            ("value", format!("```lox\n{}\n```", declaration).into()),
+                        ]),
+                    ),
+                    ("range", document.range(span)),
+                ]))
+            }
+            "textDocument/documentSymbol" => {
+                let (_, document) = self.document(params)?;
+                let symbols = document.variables.iter().map(|variable| {
+                    Json::object(vec![
+                        ("name", variable.name.as_str().into()),
+                        ("kind", SYMBOL_KIND_VARIABLE.into()),
+                        ("range", document.range(variable.declaration)),
+                        ("selectionRange", document.range(variable.name_span)),
+                    ])
+                });
+                Ok(symbols.collect::<Vec<Json>>().into())
+            }
+            "textDocument/semanticTokens/full" => {
+                let (_, document) = self.document(params)?;
+                Ok(Json::object(vec![("data", document.semantic_tokens())]))
+            }
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unknown method: {}", method),
+            }),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|document| document.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or_default()
+            .to_string();
+        match method {
+            "exit" => self.exited = true,
+            "textDocument/didOpen" => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|document| document.get("text"))
+                    .and_then(Json::as_str);
+                if let Some(text) = text {
+                    let document = Document::new(text.to_string());
+                    let diagnostics = document.diagnostics();
+                    self.documents.insert(uri.clone(), document);
+                    return vec![publish_diagnostics(&uri, diagnostics)];
+                }
+            }
+            "textDocument/didChange" => {
+                // Documents are synchronized in full, so the last change
+                // holds the whole text.
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(<[Json]>::last)
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                if let Some(text) = text {
+                    let document = Document::new(text.to_string());
+                    let diagnostics = document.diagnostics();
+                    self.documents.insert(uri.clone(), document);
+                    return vec![publish_diagnostics(&uri, diagnostics)];
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish_diagnostics(&uri, Json::Array(Vec::new()))];
+            }
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    /// The open document named by `params.textDocument.uri`.
+    fn document<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a Document), ResponseError> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|document| document.get("uri"))
+            .and_then(Json::as_str)
+            .ok_or_else(|| ResponseError::invalid_params("Missing textDocument.uri"))?;
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| ResponseError::invalid_params("Unknown document"))?;
+        Ok((uri, document))
+    }
+
+    /// The open document and byte offset named by `params.textDocument` and
+    /// `params.position`.
+    fn document_position<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a Document, usize), ResponseError> {
+        let (uri, document) = self.document(params)?;
+        let position = params.get("position");
+        let coordinate = |name: &str| {
+            position
+                .and_then(|position| position.get(name))
+                .and_then(Json::as_i64)
+                .and_then(|value| usize::try_from(value).ok())
+                .ok_or_else(|| ResponseError::invalid_params("Invalid position"))
+        };
+        let offset = document.offset(coordinate("line")?, coordinate("character")?);
+        Ok((uri, document, offset))
+    }
+}
+
+fn error_response(id: Json, error: ResponseError) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", error.code.into()),
+                ("message", error.message.into()),
+            ]),
+        ),
+    ])
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            Json::object(vec![("uri", uri.into()), ("diagnostics", diagnostics)]),
+        ),
+    ])
+}
+
+fn capabilities() -> Json {
+    let token_types = TOKEN_TYPES.iter().map(|name| Json::from(*name)).collect();
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                // Full document synchronization.
+                ("textDocumentSync", 1_i64.into()),
+                ("definitionProvider", true.into()),
+                ("referencesProvider", true.into()),
+                ("hoverProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+                (
+                    "semanticTokensProvider",
+                    Json::object(vec![
+                        (
+                            "legend",
+                            Json::object(vec![
+                                ("tokenTypes", Json::Array(token_types)),
+                                ("tokenModifiers", Json::Array(vec!["declaration".into()])),
+                            ]),
+                        ),
+                        ("full", true.into()),
+                    ]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![
+                ("name", "loxc".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+/// A variable declaration and the places it is used.
+struct Variable {
+    name: Symbol,
+    name_span: Span,
+    /// The whole `var` statement.
+    declaration: Span,
+    /// Reads and assignments, in source order.
+    references: Vec<Span>,
+}
+
+/// An open document and what is known about it.
+struct Document {
+    text: String,
+    /// Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+    tokens: Vec<Token>,
+    comments: Vec<Span>,
+    errors: Vec<Diagnostic>,
+    /// Every variable declared in the statements that parse, in source
+    /// order.
+    variables: Vec<Variable>,
+}
+
+impl Document {
+    fn new(text: String) -> Document {
+        let mut tokenizer = Tokenizer {
+            source: &text,
+            ..Default::default()
+        };
+        let result = tokenizer.scan_tokens();
+        let tokens = tokenizer.tokens;
+        let comments = tokenizer.comments;
+
+        // Both recover from errors, so whatever did parse can still be
+        // resolved.
+        let mut parser = Parser {
+            tokens: tokens.clone(),
+            ..Default::default()
+        };
+        let parse_result = parser.parse();
+        let errors = match (result, parse_result) {
+            // Syntax errors after a bad token are usually caused by it.
+            (Err(tokenize_errors), _) => tokenize_errors
+                .iter()
+                .map(TokenizeError::to_diagnostic)
+                .collect(),
+            (Ok(()), Err(parse_errors)) => {
+                parse_errors.iter().map(ParseError::to_diagnostic).collect()
+            }
+            (Ok(()), Ok(_)) => Vec::new(),
+        };
+        let mut resolver = Resolver {
+            ast: &parser.ast,
+            scopes: vec![Vec::new()],
+            variables: Vec::new(),
+        };
+        for statement in &parser.statements {
+            resolver.statement(*statement);
+        }
+        let variables = resolver.variables;
+
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i.saturating_add(1)))
+            .collect();
+        Document {
+            text,
+            line_starts,
+            tokens,
+            comments,
+            errors,
+            variables,
+        }
+    }
+
+    /// The zero-based line and UTF-16 column of a byte offset, as the
+    /// protocol counts them.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        // The first line starts at 0, so at least one start is not past `offset`.
+        let line = self
+            .line_starts
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1);
+        let character = self.text[self.line_starts[line]..offset]
+            .encode_utf16()
+            .count();
+        (line, character)
+    }
+
+    /// The byte offset of a protocol position, clamped to the end of its
+    /// line.
+    fn offset(&self, line: usize, character: usize) -> usize {
+        let Some(start) = self.line_starts.get(line).copied() else {
+            return self.text.len();
+        };
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return start.saturating_add(i);
+            }
+            units = units.saturating_add(c.len_utf16());
+        }
+        self.text.len()
+    }
+
+    fn range(&self, span: Span) -> Json {
+        let point = |offset| {
+            let (line, character) = self.position(offset);
+            Json::object(vec![("line", line.into()), ("character", character.into())])
+        };
+        Json::object(vec![("start", point(span.start)), ("end", point(span.end))])
+    }
+
+    fn location(&self, uri: &str, span: Span) -> Json {
+        Json::object(vec![("uri", uri.into()), ("range", self.range(span))])
+    }
+
+    fn diagnostics(&self) -> Json {
+        let diagnostics = self.errors.iter().map(|diagnostic| {
+            let severity: i64 = match diagnostic.severity {
+                Severity::Error => 1,
+                Severity::Warning => 2,
+            };
+            Json::object(vec![
+                ("range", self.range(diagnostic.span)),
+                ("severity", severity.into()),
+                ("code", diagnostic.code.into()),
+                ("source", "loxc".into()),
+                ("message", diagnostic.message.as_str().into()),
+            ])
+        });
+        Json::Array(diagnostics.collect())
+    }
+
+    /// The variable whose name is at `offset`, and the span of that name.
+    fn variable_at(&self, offset: usize) -> Option<(Span, &Variable)> {
+        let token = self.tokens.iter().find(|token| {
+            token.token_type == TokenType::Identifier
+                && token.span.start <= offset
+                && offset <= token.span.end
+        })?;
+        let variable = self.variables.iter().find(|variable| {
+            variable.name_span == token.span || variable.references.contains(&token.span)
+        })?;
+        Some((token.span, variable))
+    }
+
+    /// The tokens to highlight, encoded relative to each other as the
+    /// protocol asks. Tokens spanning lines are split into one per line.
+    fn semantic_tokens(&self) -> Json {
+        let mut highlights: Vec<(Span, usize, usize)> = self
+            .tokens
+            .iter()
+            .filter_map(|token| {
+                let token_type = semantic_token_type(token.token_type)?;
+                let is_declaration = self
+                    .variables
+                    .iter()
+                    .any(|variable| variable.name_span == token.span);
+                let modifiers = if is_declaration { DECLARATION } else { 0 };
+                Some((token.span, token_type, modifiers))
+            })
+            .collect();
+        highlights.extend(self.comments.iter().map(|span| (*span, COMMENT, 0)));
+        highlights.sort_by_key(|(span, _, _)| span.start);
+
+        let mut data = Vec::new();
+        let (mut previous_line, mut previous_character) = (0, 0);
+        for (span, token_type, modifiers) in highlights {
+            let mut start = span.start;
+            for piece in self.text[span.start..span.end].split('\n') {
+                let piece_start = start;
+                start = start.saturating_add(piece.len()).saturating_add(1);
+                let length = piece.encode_utf16().count();
+                if length == 0 {
+                    continue;
+                }
+                let (line, character) = self.position(piece_start);
+                let delta_character = if line == previous_line {
+                    character.saturating_sub(previous_character)
+                } else {
+                    character
+                };
+                for value in [
+                    line.saturating_sub(previous_line),
+                    delta_character,
+                    length,
+                    token_type,
+                    modifiers,
+                ] {
+                    data.push(Json::from(value));
+                }
+                (previous_line, previous_character) = (line, character);
+            }
+        }
+        Json::Array(data)
+    }
+}
+
+fn semantic_token_type(token_type: TokenType) -> Option<usize> {
+    match token_type {
+        TokenType::Identifier => Some(VARIABLE),
+        TokenType::String => Some(STRING),
+        TokenType::Number => Some(NUMBER),
+        TokenType::And
+        | TokenType::Class
+        | TokenType::Else
+        | TokenType::False
+        | TokenType::Fun
+        | TokenType::For
+        | TokenType::If
+        | TokenType::Nil
+        | TokenType::Or
+        | TokenType::Print
+        | TokenType::Return
+        | TokenType::Super
+        | TokenType::This
+        | TokenType::True
+        | TokenType::Var
+        | TokenType::While => Some(KEYWORD),
+        TokenType::Minus
+        | TokenType::Plus
+        | TokenType::Slash
+        | TokenType::Star
+        | TokenType::Bang
+        | TokenType::BangEqual
+        | TokenType::Equal
+        | TokenType::EqualEqual
+        | TokenType::Greater
+        | TokenType::GreaterEqual
+        | TokenType::Less
+        | TokenType::LessEqual => Some(OPERATOR),
+        _ => None,
+    }
+}
+
+/// Finds the declaration each variable reference resolves to.
+struct Resolver<'a> {
+    ast: &'a Ast,
+    /// Indices into `variables` of the variables in scope, innermost scope
+    /// last.
+    scopes: Vec<Vec<usize>>,
+    variables: Vec<Variable>,
+}
+
+impl<'a> Resolver<'a> {
+    fn statement(&mut self, id: StmtId) {
+        let ast = self.ast;
+        match &ast[id] {
+            Statement::Print(expression) | Statement::Expression(expression) => {
+                self.expr(*expression);
+            }
+            Statement::Var(name, initializer) => {
+                if let Some(initializer) = initializer {
+                    self.expr(*initializer);
+                }
+                self.scopes.last_mut().unwrap().push(self.variables.len());
+                self.variables.push(Variable {
+                    name: name.lexeme,
+                    name_span: name.span,
+                    declaration: ast.statement_span(id),
+                    references: Vec::new(),
+                });
+            }
+            Statement::Block(statements) => {
+                self.scopes.push(Vec::new());
+                for statement in statements {
+                    self.statement(*statement);
+                }
+                self.scopes.pop();
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.expr(*condition);
+                self.statement(*then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(*else_branch);
+                }
+            }
+            Statement::While(condition, body) => {
+                self.expr(*condition);
+                self.statement(*body);
+            }
+        }
+    }
+
+    fn expr(&mut self, id: ExprId) {
+        let ast = self.ast;
+        match &ast[id] {
+            Expr::Literal(_) => {}
+            Expr::Unary(_, operand) | Expr::Grouping(operand) => self.expr(*operand),
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.expr(*left);
+                self.expr(*right);
+            }
+            Expr::Variable(name) => self.reference(name),
+            Expr::Assign(name, value) => {
+                self.reference(name);
+                self.expr(*value);
+            }
+        }
+    }
+
+    fn reference(&mut self, name: &Token) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .copied()
+            .find(|index| self.variables[*index].name == name.lexeme);
+        if let Some(index) = found {
+            self.variables[index].references.push(name.span);
+        }
+    }
+}
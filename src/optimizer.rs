@@ -0,0 +1,175 @@
+use crate::ast::{Ast, ExprId, StmtId};
+use crate::decimal::DecimalContext;
+use crate::expr::{BinaryOp, Expr, Literal, LogicalOp, UnaryOp};
+use crate::interpreter::{expression_literal_to_value, is_truthy, numeric_binary, Value};
+use crate::statement::Statement;
+
+/// Folds constant expressions and drops branches that can never run.
+///
+/// Nodes are rewritten in place, so ids held by parents stay valid. Anything
+/// that would fail at runtime (dividing by zero, negating a string, ...) is
+/// left alone so the interpreter still reports the error.
+pub fn optimize(ast: &mut Ast, statements: &[StmtId]) -> Vec<StmtId> {
+    statements
+        .iter()
+        .copied()
+        .filter(|statement| !optimize_statement(ast, *statement))
+        .collect()
+}
+
+/// Returns whether the statement turned out to do nothing.
+fn optimize_statement(ast: &mut Ast, id: StmtId) -> bool {
+    match ast[id].clone() {
+        Statement::Print(expr) | Statement::Expression(expr) => {
+            optimize_expr(ast, expr);
+        }
+        Statement::Var(_, initializer) => {
+            if let Some(initializer) = initializer {
+                optimize_expr(ast, initializer);
+            }
+        }
+        Statement::Block(statements) => {
+            let statements = optimize(ast, &statements);
+            let is_empty = statements.is_empty();
+            ast[id] = Statement::Block(statements);
+            return is_empty;
+        }
+        Statement::If(condition, then_branch, else_branch) => {
+            optimize_expr(ast, condition);
+            let then_is_empty = optimize_statement(ast, then_branch);
+            let else_is_empty = else_branch.map(|branch| optimize_statement(ast, branch));
+
+            if let Some(condition) = literal_truthiness(ast, condition) {
+                let taken = if condition {
+                    Some(then_branch)
+                } else {
+                    else_branch
+                };
+                ast[id] = match taken {
+                    Some(branch) => ast[branch].clone(),
+                    None => Statement::Block(Vec::new()),
+                };
+                return if condition {
+                    then_is_empty
+                } else {
+                    else_is_empty.unwrap_or(true)
+                };
+            }
+        }
+        Statement::While(condition, body) => {
+            optimize_expr(ast, condition);
+            optimize_statement(ast, body);
+
+            if literal_truthiness(ast, condition) == Some(false) {
+                ast[id] = Statement::Block(Vec::new());
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+fn optimize_expr(ast: &mut Ast, id: ExprId) {
+    match ast[id].clone() {
+        Expr::Literal(_) | Expr::Variable(_) => {}
+        Expr::Grouping(inner) | Expr::Assign(_, inner) | Expr::Unary(_, inner) => {
+            optimize_expr(ast, inner);
+        }
+        Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+            optimize_expr(ast, left);
+            optimize_expr(ast, right);
+        }
+    }
+
+    if let Some(folded) = fold(ast, id) {
+        ast[id] = folded;
+    }
+}
+
+/// Simplifies a node whose children have already been optimized.
+fn fold(ast: &Ast, id: ExprId) -> Option<Expr> {
+    match &ast[id] {
+        Expr::Grouping(inner) => Some(ast[*inner].clone()),
+        Expr::Unary(operator, operand) => fold_unary(*operator, literal(ast, *operand)?),
+        Expr::Binary(left, operator, right) => {
+            fold_binary(literal(ast, *left)?, *operator, literal(ast, *right)?)
+        }
+        Expr::Logical(left, operator, right) => match (operator, literal_truthiness(ast, *left)?) {
+            (LogicalOp::Or, true) | (LogicalOp::And, false) => Some(ast[*left].clone()),
+            (LogicalOp::Or, false) | (LogicalOp::And, true) => Some(ast[*right].clone()),
+        },
+        Expr::Literal(_) | Expr::Variable(_) | Expr::Assign(_, _) => None,
+    }
+}
+
+fn literal(ast: &Ast, id: ExprId) -> Option<&Literal> {
+    match &ast[id] {
+        Expr::Literal(literal) => Some(literal),
+        _ => None,
+    }
+}
+
+fn literal_truthiness(ast: &Ast, id: ExprId) -> Option<bool> {
+    literal(ast, id).map(|literal| is_truthy(&expression_literal_to_value(literal)))
+}
+
+fn bool_literal(value: bool) -> Literal {
+    if value {
+        Literal::True
+    } else {
+        Literal::False
+    }
+}
+
+fn value_to_literal(value: Value) -> Option<Literal> {
+    match value {
+        Value::Int(n) => Some(Literal::Int(n)),
+        Value::BigInt(n) => Some(Literal::BigInt(n)),
+        Value::Decimal(n) => Some(Literal::Decimal(n)),
+        Value::Number(n) => Some(Literal::Number(n)),
+        Value::Bool(bool) => Some(bool_literal(bool)),
+        _ => None,
+    }
+}
+
+fn fold_unary(operator: UnaryOp, operand: &Literal) -> Option<Expr> {
+    let literal = match (operator, operand) {
+        (UnaryOp::Minus, Literal::Int(n)) => Literal::Int(n.checked_neg()?),
+        (UnaryOp::Minus, Literal::Number(n)) => Literal::Number(-n),
+        // Exact numbers have no fixed width to overflow.
+        #[allow(clippy::arithmetic_side_effects)]
+        (UnaryOp::Minus, Literal::BigInt(n)) => Literal::BigInt(-n),
+        #[allow(clippy::arithmetic_side_effects)]
+        (UnaryOp::Minus, Literal::Decimal(n)) => Literal::Decimal(-n),
+        (UnaryOp::Minus, _) => return None,
+        (UnaryOp::Bang, literal) => bool_literal(!is_truthy(&expression_literal_to_value(literal))),
+    };
+    Some(Expr::Literal(literal))
+}
+
+fn fold_binary(left: &Literal, operator: BinaryOp, right: &Literal) -> Option<Expr> {
+    // Inexact exact-number division depends on the interpreter's decimal
+    // context, which is not known here.
+    let is_exact = |literal: &Literal| matches!(literal, Literal::BigInt(_) | Literal::Decimal(_));
+    if operator == BinaryOp::Slash && (is_exact(left) || is_exact(right)) {
+        return None;
+    }
+
+    let left_value = expression_literal_to_value(left);
+    let right_value = expression_literal_to_value(right);
+    let context = DecimalContext::default();
+    if let Some(result) = numeric_binary(&left_value, operator, &right_value, &context) {
+        // Errors such as division by zero are left for the interpreter.
+        return value_to_literal(result.ok()?).map(Expr::Literal);
+    }
+
+    // Concatenations are left alone: their result would have to be interned,
+    // and interned strings are never freed. At runtime it is a heap object.
+    let literal = match (left, operator, right) {
+        (Literal::Str(s1), BinaryOp::EqualEqual, Literal::Str(s2)) => bool_literal(s1 == s2),
+        (Literal::Str(s1), BinaryOp::BangEqual, Literal::Str(s2)) => bool_literal(s1 != s2),
+        _ => return None,
+    };
+    Some(Expr::Literal(literal))
+}
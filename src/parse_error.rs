@@ -1,18 +1,151 @@
 use std::fmt;
 
+use thiserror::Error;
+
+use crate::diagnostic::Diagnostic;
+use crate::span::Span;
+use crate::token::token::Token;
 use crate::token::token_type::TokenType;
 
-#[derive(Debug)]
-pub enum ParseError {
-    UnexpectedTokenError(TokenType),
+/// The construct the parser was in the middle of when it found an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    VarDeclaration,
+    ForClauses,
+    WhileCondition,
+    IfCondition,
+    Block,
+    PrintStatement,
+    ExpressionStatement,
+    Grouping,
 }
 
-impl fmt::Display for ParseError {
+impl fmt::Display for Context {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        return match self {
-            Self::UnexpectedTokenError(token_type) => {
-                write!(f, "Parse Error: Unexpected token: {}", token_type)
+        let description = match self {
+            Context::VarDeclaration => "in variable declaration",
+            Context::ForClauses => "in for clauses",
+            Context::WhileCondition => "in while condition",
+            Context::IfCondition => "in if condition",
+            Context::Block => "in block",
+            Context::PrintStatement => "in print statement",
+            Context::ExpressionStatement => "in expression statement",
+            Context::Grouping => "in parenthesized expression",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum ParseError {
+    /// A required token is missing.
+    #[error("Parse Error: {message} at line {}", found.span.line)]
+    ExpectedTokenError {
+        expected: Vec<TokenType>,
+        /// What to tell the user, e.g. "Expect ';' after value.".
+        message: &'static str,
+        found: Token,
+        context: Option<Context>,
+    },
+    /// An expression was required but `found` cannot start one.
+    #[error("Parse Error: Expect expression. at line {}", found.span.line)]
+    ExpectedExpressionError {
+        found: Token,
+        context: Option<Context>,
+    },
+    /// The left-hand side of `=` is not something that can be assigned to.
+    #[error("Parse Error: Invalid assignment target. at line {}", target.line)]
+    InvalidAssignmentTargetError { target: Span, equals: Token },
+    #[error("Parse Error: Unexpected token: {} at line {}", found.token_type, found.span.line)]
+    UnexpectedTokenError {
+        found: Token,
+        context: Option<Context>,
+    },
+    #[error("Parse Error: Too deeply nested at line {}", found.span.line)]
+    TooDeeplyNestedError { found: Token },
+}
+
+impl ParseError {
+    /// Stable identifier of this kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ExpectedTokenError { .. } => "E0101",
+            Self::ExpectedExpressionError { .. } => "E0102",
+            Self::InvalidAssignmentTargetError { .. } => "E0103",
+            Self::UnexpectedTokenError { .. } => "E0104",
+            Self::TooDeeplyNestedError { .. } => "E0105",
+        }
+    }
+
+    /// The token the parser stopped at.
+    pub fn found(&self) -> &Token {
+        match self {
+            Self::ExpectedTokenError { found, .. }
+            | Self::ExpectedExpressionError { found, .. }
+            | Self::UnexpectedTokenError { found, .. }
+            | Self::TooDeeplyNestedError { found } => found,
+            Self::InvalidAssignmentTargetError { equals, .. } => equals,
+        }
+    }
+
+    /// Where the error should be reported.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::InvalidAssignmentTargetError { target, .. } => *target,
+            _ => self.found().span,
+        }
+    }
+
+    pub fn context(&self) -> Option<Context> {
+        match self {
+            Self::ExpectedTokenError { context, .. }
+            | Self::ExpectedExpressionError { context, .. }
+            | Self::UnexpectedTokenError { context, .. } => *context,
+            Self::InvalidAssignmentTargetError { .. } | Self::TooDeeplyNestedError { .. } => None,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::ExpectedTokenError { message, .. } => message.to_string(),
+            Self::ExpectedExpressionError { .. } => String::from("Expect expression."),
+            Self::InvalidAssignmentTargetError { .. } => String::from("Invalid assignment target."),
+            Self::UnexpectedTokenError { found, .. } => {
+                format!("Unexpected token: {}", found.token_type)
+            }
+            Self::TooDeeplyNestedError { .. } => String::from("Too deeply nested"),
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let found = self.found();
+        let mut label = if found.token_type == TokenType::EOF {
+            String::from("found end of file")
+        } else {
+            format!("found `{}`", found.lexeme)
+        };
+        if let Some(context) = self.context() {
+            label = format!("{} {}", label, context);
+        }
+
+        let (label, help) = match self {
+            Self::InvalidAssignmentTargetError { .. } => {
+                (String::from("cannot assign to this"), vec![])
             }
+            Self::TooDeeplyNestedError { .. } => (
+                label,
+                vec![String::from("the limit can be raised with --max-depth")],
+            ),
+            _ => (label, vec![]),
         };
+        Diagnostic {
+            code: Some(self.code()),
+            message: self.message(),
+            span: self.span(),
+            label: Some(label),
+            help,
+            ..Default::default()
+        }
     }
 }
@@ -1,42 +1,143 @@
 #![allow(clippy::needless_return)]
 
+use crate::ast::{Ast, ExprId, ForLoop, StmtId};
+use crate::cst::{CstBuilder, SyntaxKind};
 use crate::expr::{BinaryOp, Expr, Literal, LogicalOp, UnaryOp};
-use crate::parse_error::ParseError;
+use crate::parse_error::{Context, ParseError};
+use crate::span::Span;
 use crate::statement::Statement;
 use crate::token::token;
 use crate::token::token::Token;
 use crate::token::token_type::TokenType;
 
-#[derive(Default)]
+/// Default for [`Parser::max_depth`], low enough to stay clear of the native
+/// stack limit even on the 2 MiB stacks of spawned threads.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Errors are boxed while they propagate, as a large `Err` would make every
+/// frame of the recursive descent bigger.
+type ParseResult<T> = Result<T, Box<ParseError>>;
+
 pub struct Parser {
     pub tokens: Vec<Token>,
     pub current: usize,
+    pub ast: Ast,
+    /// Top-level statements parsed so far. Unlike the result of
+    /// [`Parser::parse`], they are kept when there are syntax errors.
+    pub statements: Vec<StmtId>,
+    /// Deepest nesting of statements and expressions accepted before failing
+    /// with [`ParseError::TooDeeplyNestedError`].
+    pub max_depth: usize,
+    pub depth: usize,
+    /// Syntax errors reported so far; parsing resumes after each one.
+    pub errors: Vec<ParseError>,
+    /// Constructs currently being parsed, innermost last.
+    pub context: Vec<Context>,
+    /// Set once nesting exceeded `max_depth`; parsing stops there.
+    pub too_deep: bool,
+    /// Builds a concrete syntax tree alongside the AST when set.
+    pub cst: Option<CstBuilder>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            tokens: Vec::new(),
+            current: 0,
+            ast: Ast::default(),
+            statements: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth: 0,
+            errors: Vec::new(),
+            context: Vec::new(),
+            too_deep: false,
+            cst: None,
+        }
+    }
 }
 
 impl Parser {
-    pub fn parse(&mut self) -> Result<Vec<Statement>, ParseError> {
-        let mut statements = Vec::new();
+    /// Parses the whole program, or returns every syntax error in it in
+    /// source order.
+    pub fn parse(&mut self) -> Result<Vec<StmtId>, Vec<ParseError>> {
         while !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(statement) = self.declaration() {
+                self.statements.push(statement);
+            }
+        }
+        // The end of file token carries the trivia at the end of the source.
+        if let Some(cst) = &mut self.cst {
+            cst.token(self.current, &self.tokens[self.current]);
         }
 
-        return Ok(statements);
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
+        }
+        return Ok(self.statements.clone());
     }
 
-    fn declaration(&mut self) -> Result<Statement, ParseError> {
-        if self.match_token_type(TokenType::Var) {
-            self.var_declaration()
+    /// Parses a declaration, recording any syntax error and skipping to the
+    /// start of the next statement so the rest of the file still gets checked.
+    fn declaration(&mut self) -> Option<StmtId> {
+        let checkpoint = self.checkpoint();
+        let depth = self.depth;
+        let is_var = self.match_token_type(TokenType::Var);
+        let result = if is_var {
+            self.in_context(Context::VarDeclaration, Self::var_declaration)
         } else {
             self.statement()
+        };
+
+        match result {
+            Ok(statement) => {
+                if is_var {
+                    self.finish_node(checkpoint, SyntaxKind::VarDeclaration);
+                }
+                Some(statement)
+            }
+            Err(err) => {
+                // Operator chains leave their extra depth behind when an
+                // error unwinds them.
+                self.depth = depth;
+                if !self.too_deep {
+                    self.too_deep = matches!(*err, ParseError::TooDeeplyNestedError { .. });
+                    self.errors.push(*err);
+                }
+                if self.too_deep {
+                    // Every enclosing construct would fail too, so give up on
+                    // the rest of the source instead of reporting each one.
+                    while !self.is_at_end() {
+                        self.advance();
+                    }
+                } else {
+                    self.synchronize();
+                }
+                self.finish_node(checkpoint, SyntaxKind::Error);
+                None
+            }
+        }
+    }
+
+    /// Where a node of the concrete syntax tree may start.
+    fn checkpoint(&self) -> usize {
+        self.cst.as_ref().map_or(0, CstBuilder::checkpoint)
+    }
+
+    /// Wraps everything consumed since `checkpoint` into a `kind` node of the
+    /// concrete syntax tree, if one is being built.
+    fn finish_node(&mut self, checkpoint: usize, kind: SyntaxKind) {
+        if let Some(cst) = &mut self.cst {
+            cst.wrap(checkpoint, kind);
         }
     }
 
-    fn consume_identifier(&mut self) -> Result<Token, ParseError> {
+    fn consume_identifier(&mut self) -> ParseResult<Token> {
         let token = self.consume(TokenType::Identifier, "Expect variable name.")?;
         Ok(token.clone())
     }
 
-    fn var_declaration<'a>(&mut self) -> Result<Statement, ParseError> {
+    fn var_declaration(&mut self) -> ParseResult<StmtId> {
+        let start = self.previous().span;
         let name = self.consume_identifier()?;
 
         let initializer = if self.match_token_type(TokenType::Equal) {
@@ -50,42 +151,124 @@ impl Parser {
             "Expect ';' after variable declaration.",
         )?;
 
-        Ok(Statement::Var(name, initializer))
+        Ok(self.add_statement(Statement::Var(name, initializer), start))
     }
 
-    fn statement(&mut self) -> Result<Statement, ParseError> {
-        if self.match_token_type(TokenType::For) {
-            return self.for_statement();
-        }
+    /// Runs `f` one nesting level deeper, failing once `max_depth` is reached.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        self.deepen()?;
+        let result = f(self);
+        self.depth = self.depth.saturating_sub(1);
+        result
+    }
 
-        if self.match_token_type(TokenType::If) {
-            return self.if_statement();
+    /// Goes one nesting level deeper, failing once `max_depth` is reached.
+    /// Operator chains call this for every operand after the first, as each
+    /// of them makes the left-leaning tree one level deeper; they restore
+    /// `depth` once the chain ends.
+    fn deepen(&mut self) -> ParseResult<()> {
+        if self.depth >= self.max_depth {
+            return Err(ParseError::TooDeeplyNestedError {
+                found: self.peek().clone(),
+            }
+            .into());
+        }
+        self.depth = self.depth.saturating_add(1);
+        Ok(())
+    }
+
+    /// Runs `f` with `context` as the innermost construct for error messages.
+    fn in_context<T>(
+        &mut self,
+        context: Context,
+        f: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<T> {
+        self.context.push(context);
+        let result = f(self);
+        self.context.pop();
+        result
+    }
+
+    fn statement(&mut self) -> ParseResult<StmtId> {
+        self.nested(Self::nested_statement)
+    }
+
+    fn nested_statement(&mut self) -> ParseResult<StmtId> {
+        let checkpoint = self.checkpoint();
+        let (kind, statement) = if self.match_token_type(TokenType::For) {
+            (SyntaxKind::ForStatement, self.for_statement()?)
+        } else if self.match_token_type(TokenType::If) {
+            (SyntaxKind::IfStatement, self.if_statement()?)
+        } else if self.match_token_type(TokenType::Print) {
+            let statement = self.in_context(Context::PrintStatement, Self::print_statement)?;
+            (SyntaxKind::PrintStatement, statement)
+        } else if self.match_token_type(TokenType::While) {
+            (SyntaxKind::WhileStatement, self.while_statement()?)
+        } else if self.match_token_type(TokenType::LeftBrace) {
+            let start = self.previous().span;
+            let statements = self.in_context(Context::Block, Self::block)?;
+            let statement = self.add_statement(Statement::Block(statements), start);
+            (SyntaxKind::Block, statement)
+        } else {
+            let statement =
+                self.in_context(Context::ExpressionStatement, Self::expression_statement)?;
+            (SyntaxKind::ExpressionStatement, statement)
         };
 
-        if self.match_token_type(TokenType::Print) {
-            return self.print_statement();
+        self.finish_node(checkpoint, kind);
+        return Ok(statement);
+    }
+
+    fn for_statement(&mut self) -> ParseResult<StmtId> {
+        let start = self.previous().span;
+        let (initializer, condition, increment) =
+            self.in_context(Context::ForClauses, Self::for_clauses)?;
+        let mut body = self.statement()?;
+        let for_loop = ForLoop {
+            initializer,
+            condition,
+            increment,
+            body,
+        };
+
+        // The desugared nodes all point at the whole `for` statement.
+        if let Some(increment) = increment {
+            let span = self.ast.expr_span(increment);
+            let increment = self
+                .ast
+                .add_statement(Statement::Expression(increment), span);
+            body = self.add_statement(Statement::Block(vec![body, increment]), start);
         }
 
-        if self.match_token_type(TokenType::While) {
-            return self.while_statement();
+        let condition = match condition {
+            Some(condition) => condition,
+            None => self.ast.add_expr(Expr::Literal(Literal::True), start),
         };
+        body = self.add_statement(Statement::While(condition, body), start);
 
-        if self.match_token_type(TokenType::LeftBrace) {
-            return Ok(Statement::Block(self.block()?));
+        if let Some(initializer) = initializer {
+            body = self.add_statement(Statement::Block(vec![initializer, body]), start);
         }
 
-        return self.expression_statement();
+        self.ast.add_for_loop(body, for_loop);
+        return Ok(body);
     }
 
-    fn for_statement(&mut self) -> Result<Statement, ParseError> {
+    /// Parses `(initializer; condition; increment)` of a `for` loop.
+    fn for_clauses(&mut self) -> ParseResult<(Option<StmtId>, Option<ExprId>, Option<ExprId>)> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
+        let checkpoint = self.checkpoint();
         let initializer = if self.match_token_type(TokenType::Semicolon) {
             None
         } else if self.match_token_type(TokenType::Var) {
-            Some(self.var_declaration())
+            let initializer = self.var_declaration()?;
+            self.finish_node(checkpoint, SyntaxKind::VarDeclaration);
+            Some(initializer)
         } else {
-            Some(self.expression_statement())
+            let initializer = self.expression_statement()?;
+            self.finish_node(checkpoint, SyntaxKind::ExpressionStatement);
+            Some(initializer)
         };
 
         let mut condition = None;
@@ -100,127 +283,142 @@ impl Parser {
         }
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-
-        if increment.is_some() {
-            body = Statement::Block(vec![body, Statement::Expression(increment.unwrap())]);
-        }
-
-        if condition.is_none() {
-            condition = Some(Expr::Literal(Literal::True));
-        }
-        body = Statement::While(condition.unwrap(), Box::new(body));
-
-        match initializer {
-            Some(initializer) => body = Statement::Block(vec![initializer.unwrap(), body]),
-            None => (),
-        }
-
-        return Ok(body);
+        return Ok((initializer, condition, increment));
     }
 
-    fn while_statement(&mut self) -> Result<Statement, ParseError> {
-        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
-        let condition = self.expression()?;
-        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+    fn while_statement(&mut self) -> ParseResult<StmtId> {
+        let start = self.previous().span;
+        let condition = self.in_context(Context::WhileCondition, |parser| {
+            parser.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+            let condition = parser.expression()?;
+            parser.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+            Ok(condition)
+        })?;
         let body = self.statement()?;
 
-        return Ok(Statement::While(condition, Box::new(body)));
+        return Ok(self.add_statement(Statement::While(condition, body), start));
     }
 
-    fn if_statement(&mut self) -> Result<Statement, ParseError> {
-        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
-        let condition = self.expression()?;
-        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+    fn if_statement(&mut self) -> ParseResult<StmtId> {
+        let start = self.previous().span;
+        let condition = self.in_context(Context::IfCondition, |parser| {
+            parser.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+            let condition = parser.expression()?;
+            parser.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+            Ok(condition)
+        })?;
 
         let then_branch = self.statement()?;
         let else_branch = if self.match_token_type(TokenType::Else) {
-            Some(Box::new(self.statement()?))
+            Some(self.statement()?)
         } else {
             None
         };
 
-        Ok(Statement::If(condition, Box::new(then_branch), else_branch))
+        Ok(self.add_statement(Statement::If(condition, then_branch, else_branch), start))
     }
 
-    fn block(&mut self) -> Result<Vec<Statement>, ParseError> {
+    fn block(&mut self) -> ParseResult<Vec<StmtId>> {
         let mut statements = Vec::new();
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            statements.push(self.declaration()?);
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
         }
 
-        self.consume(TokenType::RightBrace, "Expect '}' after block.");
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
         return Ok(statements);
     }
 
-    fn print_statement(&mut self) -> Result<Statement, ParseError> {
+    fn print_statement(&mut self) -> ParseResult<StmtId> {
+        let start = self.previous().span;
         let value = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
-        Ok(Statement::Print(value))
+        Ok(self.add_statement(Statement::Print(value), start))
     }
 
-    fn expression_statement(&mut self) -> Result<Statement, ParseError> {
+    fn expression_statement(&mut self) -> ParseResult<StmtId> {
         let expr = self.expression()?;
         self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
-        Ok(Statement::Expression(expr))
+        let start = self.ast.expr_span(expr);
+        Ok(self.add_statement(Statement::Expression(expr), start))
     }
 
-    fn expression(&mut self) -> Result<Expr, ParseError> {
-        return self.assignment();
+    fn expression(&mut self) -> ParseResult<ExprId> {
+        return self.nested(Self::assignment);
     }
 
-    fn and(&mut self) -> Result<Expr, ParseError> {
+    fn and(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.equality()?;
 
+        let depth = self.depth;
         while self.match_token_type(TokenType::And) {
             let operator = self.previous();
             let maybe_logical_operator = Parser::token_to_logical_operator(operator);
+            self.deepen()?;
             let right = self.equality()?;
 
             match maybe_logical_operator {
-                Ok(operator) => expr = Expr::Logical(Box::new(expr), operator, Box::new(right)),
+                Ok(operator) => {
+                    expr = self.add_binary(Expr::Logical(expr, operator, right), expr, right);
+                    self.finish_node(checkpoint, SyntaxKind::LogicalExpr);
+                }
                 Err(err) => return Err(err),
             }
         }
+        self.depth = depth;
 
         return Ok(expr);
     }
 
-    fn or(&mut self) -> Result<Expr, ParseError> {
+    fn or(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.and()?;
 
+        let depth = self.depth;
         while self.match_token_type(TokenType::Or) {
             let operator = self.previous();
             let maybe_logical_operator = Parser::token_to_logical_operator(operator);
+            self.deepen()?;
             let right = self.and()?;
 
             match maybe_logical_operator {
-                Ok(operator) => expr = Expr::Logical(Box::new(expr), operator, Box::new(right)),
+                Ok(operator) => {
+                    expr = self.add_binary(Expr::Logical(expr, operator, right), expr, right);
+                    self.finish_node(checkpoint, SyntaxKind::LogicalExpr);
+                }
                 Err(err) => return Err(err),
             }
         }
+        self.depth = depth;
 
         return Ok(expr);
     }
 
-    fn assignment(&mut self) -> Result<Expr, ParseError> {
-        let expr = self.or();
+    fn assignment(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
+        let expr = self.or()?;
 
         if self.match_token_type(TokenType::Equal) {
-            return match expr {
-                Ok(Expr::Variable(name)) => {
-                    let value = self.assignment()?;
-                    Ok(Expr::Assign(name, Box::new(value)))
-                }
-                _ => {
-                    let equals = self.previous();
-                    Err(ParseError::UnexpectedTokenError(equals.token_type))
-                }
-            };
+            let equals = self.previous().clone();
+            let value = self.nested(Self::assignment)?;
+            self.finish_node(checkpoint, SyntaxKind::AssignExpr);
+
+            if let Expr::Variable(name) = &self.ast[expr] {
+                let name = name.clone();
+                return Ok(self.add_expr(Expr::Assign(name.clone(), value), name.span));
+            }
+
+            // Reported without unwinding, as the parser still knows where it is.
+            self.errors.push(ParseError::InvalidAssignmentTargetError {
+                target: self.ast.expr_span(expr),
+                equals,
+            });
         }
 
-        return expr;
+        return Ok(expr);
     }
 
     fn match_equality_token(&mut self) -> bool {
@@ -232,21 +430,26 @@ impl Parser {
         return false;
     }
 
-    fn equality(&mut self) -> Result<Expr, ParseError> {
+    fn equality(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.comparison()?;
+        let depth = self.depth;
         while self.match_equality_token() {
             let operator = self.previous();
             let maybe_binary_operator = Parser::token_to_binary_operator(operator);
 
+            self.deepen()?;
             let right = self.comparison()?;
 
             match maybe_binary_operator {
                 Ok(binary_operator) => {
-                    expr = Expr::Binary(Box::new(expr), binary_operator, Box::new(right))
+                    expr = self.add_binary(Expr::Binary(expr, binary_operator, right), expr, right);
+                    self.finish_node(checkpoint, SyntaxKind::BinaryExpr);
                 }
                 Err(err) => return Err(err),
             }
         }
+        self.depth = depth;
 
         return Ok(expr);
     }
@@ -264,22 +467,27 @@ impl Parser {
         return false;
     }
 
-    fn comparison(&mut self) -> Result<Expr, ParseError> {
+    fn comparison(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.term()?;
 
+        let depth = self.depth;
         while self.match_comparision_token() {
             let operator = self.previous();
             let maybe_binary_operator = Parser::token_to_binary_operator(operator);
 
+            self.deepen()?;
             let right = self.term()?;
 
             match maybe_binary_operator {
                 Ok(binary_operator) => {
-                    expr = Expr::Binary(Box::new(expr), binary_operator, Box::new(right));
+                    expr = self.add_binary(Expr::Binary(expr, binary_operator, right), expr, right);
+                    self.finish_node(checkpoint, SyntaxKind::BinaryExpr);
                 }
                 Err(err) => return Err(err),
             }
         }
+        self.depth = depth;
 
         return Ok(expr);
     }
@@ -293,22 +501,27 @@ impl Parser {
         return false;
     }
 
-    fn term(&mut self) -> Result<Expr, ParseError> {
+    fn term(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.factor()?;
 
+        let depth = self.depth;
         while self.match_term_token() {
             let operator = self.previous();
             let maybe_binary_operator = Parser::token_to_binary_operator(operator);
 
+            self.deepen()?;
             let right = self.factor()?;
 
             match maybe_binary_operator {
                 Ok(binary_operator) => {
-                    expr = Expr::Binary(Box::new(expr), binary_operator, Box::new(right))
+                    expr = self.add_binary(Expr::Binary(expr, binary_operator, right), expr, right);
+                    self.finish_node(checkpoint, SyntaxKind::BinaryExpr);
                 }
                 Err(err) => return Err(err),
             }
         }
+        self.depth = depth;
 
         return Ok(expr);
     }
@@ -322,21 +535,26 @@ impl Parser {
         return false;
     }
 
-    fn factor(&mut self) -> Result<Expr, ParseError> {
+    fn factor(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         let mut expr = self.unary()?;
 
+        let depth = self.depth;
         while self.match_factor_token() {
             let operator = self.previous();
             let maybe_binary_operator = Parser::token_to_binary_operator(operator);
+            self.deepen()?;
             let right = self.unary()?;
 
             match maybe_binary_operator {
                 Ok(binary_operator) => {
-                    expr = Expr::Binary(Box::new(expr), binary_operator, Box::new(right))
+                    expr = self.add_binary(Expr::Binary(expr, binary_operator, right), expr, right);
+                    self.finish_node(checkpoint, SyntaxKind::BinaryExpr);
                 }
                 Err(err) => return Err(err),
             }
         }
+        self.depth = depth;
 
         return Ok(expr);
     }
@@ -350,15 +568,20 @@ impl Parser {
         return false;
     }
 
-    fn unary(&mut self) -> Result<Expr, ParseError> {
+    fn unary(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
         if self.match_unary_token() {
+            let start = self.previous().span;
             let operator = self.previous();
             let maybe_binary_operator = Parser::token_to_unary_operator(operator);
 
-            let right = self.unary()?;
+            let right = self.nested(Self::unary)?;
+            self.finish_node(checkpoint, SyntaxKind::UnaryExpr);
 
             return match maybe_binary_operator {
-                Ok(binary_operator) => Ok(Expr::Unary(binary_operator, Box::new(right))),
+                Ok(binary_operator) => {
+                    Ok(self.add_expr(Expr::Unary(binary_operator, right), start))
+                }
                 Err(err) => Err(err),
             };
         }
@@ -384,42 +607,82 @@ impl Parser {
         return false;
     }
 
-    fn primary(&mut self) -> Result<Expr, ParseError> {
+    fn primary(&mut self) -> ParseResult<ExprId> {
+        let checkpoint = self.checkpoint();
+        let expr = self.primary_expr()?;
+        let kind = match self.ast[expr] {
+            Expr::Grouping(_) => SyntaxKind::GroupingExpr,
+            Expr::Variable(_) => SyntaxKind::VariableExpr,
+            _ => SyntaxKind::LiteralExpr,
+        };
+        self.finish_node(checkpoint, kind);
+        return Ok(expr);
+    }
+
+    fn primary_expr(&mut self) -> ParseResult<ExprId> {
         if self.match_token_type(TokenType::False) {
-            return Ok(Expr::Literal(Literal::False));
+            return Ok(self.add_expr(Expr::Literal(Literal::False), self.previous().span));
         }
         if self.match_token_type(TokenType::True) {
-            return Ok(Expr::Literal(Literal::True));
+            return Ok(self.add_expr(Expr::Literal(Literal::True), self.previous().span));
         }
         if self.match_token_type(TokenType::Nil) {
-            return Ok(Expr::Literal(Literal::Nil));
+            return Ok(self.add_expr(Expr::Literal(Literal::Nil), self.previous().span));
         }
 
         if self.match_literal_token_type() {
-            match &self.previous().literal {
-                Some(token::Literal::Number(n)) => return Ok(Expr::Literal(Literal::Number(*n))),
-                Some(token::Literal::Str(string)) => {
-                    return Ok(Expr::Literal(Literal::Str(string.to_string())));
-                }
+            let literal = match &self.previous().literal {
+                Some(token::Literal::Integer(n)) => Literal::Int(*n),
+                Some(token::Literal::BigInt(n)) => Literal::BigInt(n.clone()),
+                Some(token::Literal::Decimal(n)) => Literal::Decimal(n.clone()),
+                Some(token::Literal::Number(n)) => Literal::Number(*n),
+                Some(token::Literal::Str(string)) => Literal::Str(*string),
                 Some(other) => panic!("Failed to parse expected number: {}", other),
                 None => panic!("Failed to parse number"),
-            }
+            };
+            return Ok(self.add_expr(Expr::Literal(literal), self.previous().span));
         }
         if self.match_token_type(TokenType::Identifier) {
-            return Ok(Expr::Variable(self.previous().clone()));
+            let name = self.previous().clone();
+            return Ok(self.add_expr(Expr::Variable(name.clone()), name.span));
         }
         if self.match_token_type(TokenType::LeftParen) {
-            let expr = Box::new(self.expression()?);
-            return match self.consume(TokenType::RightParen, "Expect ')' after expression.") {
-                Ok(_) => Ok(Expr::Grouping(expr)),
-                Err(err) => Err(err),
-            };
+            let start = self.previous().span;
+            let expr = self.in_context(Context::Grouping, |parser| {
+                let expr = parser.expression()?;
+                parser.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+                Ok(expr)
+            })?;
+            return Ok(self.add_expr(Expr::Grouping(expr), start));
         }
 
-        Err(ParseError::UnexpectedTokenError(self.peek().token_type))
+        Err(ParseError::ExpectedExpressionError {
+            found: self.peek().clone(),
+            context: self.context.last().copied(),
+        }
+        .into())
+    }
+
+    /// Adds an expression spanning from `start` to the last consumed token.
+    fn add_expr(&mut self, expr: Expr, start: Span) -> ExprId {
+        let span = start.to(self.previous().span);
+        return self.ast.add_expr(expr, span);
     }
 
-    fn synchronize(mut self) {
+    /// Adds an expression spanning its `left` and `right` operands.
+    fn add_binary(&mut self, expr: Expr, left: ExprId, right: ExprId) -> ExprId {
+        let span = self.ast.expr_span(left).to(self.ast.expr_span(right));
+        return self.ast.add_expr(expr, span);
+    }
+
+    /// Adds a statement spanning from `start` to the last consumed token.
+    fn add_statement(&mut self, statement: Statement, start: Span) -> StmtId {
+        let span = start.to(self.previous().span);
+        return self.ast.add_statement(statement, span);
+    }
+
+    /// Discards tokens until what is probably the start of a new statement.
+    fn synchronize(&mut self) {
         self.advance();
 
         while !self.is_at_end() {
@@ -445,15 +708,19 @@ impl Parser {
         }
     }
 
-    fn token_to_logical_operator(token: &Token) -> Result<LogicalOp, ParseError> {
+    fn token_to_logical_operator(token: &Token) -> ParseResult<LogicalOp> {
         match token.token_type {
             TokenType::Or => Ok(LogicalOp::Or),
             TokenType::And => Ok(LogicalOp::And),
-            _ => Err(ParseError::UnexpectedTokenError(token.token_type)),
+            _ => Err(ParseError::UnexpectedTokenError {
+                found: token.clone(),
+                context: None,
+            }
+            .into()),
         }
     }
 
-    pub fn token_to_binary_operator(token: &Token) -> Result<BinaryOp, ParseError> {
+    pub fn token_to_binary_operator(token: &Token) -> ParseResult<BinaryOp> {
         match token.token_type {
             TokenType::Plus => Ok(BinaryOp::Plus),
             TokenType::Minus => Ok(BinaryOp::Minus),
@@ -465,26 +732,39 @@ impl Parser {
             TokenType::LessEqual => Ok(BinaryOp::LessEqual),
             TokenType::Greater => Ok(BinaryOp::Greater),
             TokenType::GreaterEqual => Ok(BinaryOp::GreaterEqual),
-            _ => Err(ParseError::UnexpectedTokenError(token.token_type)),
+            _ => Err(ParseError::UnexpectedTokenError {
+                found: token.clone(),
+                context: None,
+            }
+            .into()),
         }
     }
 
-    pub fn token_to_unary_operator(token: &Token) -> Result<UnaryOp, ParseError> {
+    pub fn token_to_unary_operator(token: &Token) -> ParseResult<UnaryOp> {
         match token.token_type {
             TokenType::Minus => Ok(UnaryOp::Minus),
             TokenType::Bang => Ok(UnaryOp::Bang),
-            _ => Err(ParseError::UnexpectedTokenError(token.token_type)),
+            _ => Err(ParseError::UnexpectedTokenError {
+                found: token.clone(),
+                context: None,
+            }
+            .into()),
         }
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<&Token, ParseError> {
+    fn consume(&mut self, token_type: TokenType, message: &'static str) -> ParseResult<&Token> {
         if self.check(token_type) {
             let token = self.advance();
             return Ok(token);
         }
 
-        eprintln!("{}", message);
-        return Err(ParseError::UnexpectedTokenError(self.peek().token_type));
+        return Err(ParseError::ExpectedTokenError {
+            expected: vec![token_type],
+            message,
+            found: self.peek().clone(),
+            context: self.context.last().copied(),
+        }
+        .into());
     }
 
     fn check(&self, token_type: TokenType) -> bool {
@@ -496,7 +776,10 @@ impl Parser {
 
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
-            self.current += 1;
+            if let Some(cst) = &mut self.cst {
+                cst.token(self.current, &self.tokens[self.current]);
+            }
+            self.current = self.current.saturating_add(1);
         };
         return self.previous();
     }
@@ -510,6 +793,6 @@ impl Parser {
     }
 
     fn previous(&self) -> &Token {
-        return &self.tokens[self.current - 1];
+        return &self.tokens[self.current.saturating_sub(1)];
     }
 }
@@ -0,0 +1,304 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use crate::diagnostic::ColorChoice;
+use crate::interpreter::Interpreter;
+use crate::line_editor::{Input, LineEditor, RawMode};
+use crate::parser::Parser;
+use crate::statement::Statement;
+use crate::token::token_type::TokenType;
+use crate::tokenize_error::TokenizeError;
+use crate::tokenizer::Tokenizer;
+use crate::Options;
+
+/// File name used in diagnostics for code typed at the prompt.
+const REPL_FILE: &str = "<repl>";
+
+/// Name of the history file kept in the home directory.
+pub const HISTORY_FILE: &str = ".lox_history";
+
+/// `~/.lox_history`, if the home directory is known.
+pub fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+/// An interactive session. Every input runs in the same interpreter, so
+/// variables defined by one line are visible to the next.
+///
+/// Input whose parentheses, braces or strings are still open is buffered
+/// until a later line closes them. Lines starting with `:` are meta-commands:
+///
+/// - `:load <file>` runs a file in the session
+/// - `:env` lists the defined variables
+/// - `:reset` forgets all variables
+/// - `:quit` ends the session
+pub struct Repl {
+    pub options: Options,
+    pub color: ColorChoice,
+    interpreter: Interpreter,
+    /// Lines of an input that is not complete yet.
+    pending: String,
+}
+
+impl Repl {
+    pub fn new(options: Options) -> Repl {
+        Repl {
+            interpreter: new_interpreter(&options),
+            options,
+            color: ColorChoice::Never,
+            pending: String::new(),
+        }
+    }
+
+    /// What to show before reading the next line.
+    pub fn prompt(&self) -> &'static str {
+        if self.pending.is_empty() {
+            "> "
+        } else {
+            "... "
+        }
+    }
+
+    /// Reads lines from `input` until it ends or `:quit` is entered. Results
+    /// go to `out`; prompts and errors go to `err`.
+    pub fn run(
+        &mut self,
+        input: impl BufRead,
+        out: &mut impl Write,
+        err: &mut impl Write,
+    ) -> io::Result<()> {
+        write!(err, "{}", self.prompt())?;
+        err.flush()?;
+        for line in input.lines() {
+            if !self.feed(&line?, out, err)? {
+                return Ok(());
+            }
+            write!(err, "{}", self.prompt())?;
+            err.flush()?;
+        }
+
+        // Run whatever is left so its errors are reported.
+        if !self.pending.is_empty() {
+            let source = std::mem::take(&mut self.pending);
+            self.eval(&source, REPL_FILE, true, out, err)?;
+        }
+        writeln!(err)?;
+        Ok(())
+    }
+
+    /// Like [`Repl::run`], but reads from the terminal with `editor`, which
+    /// completes keywords and defined variable names. Falls back to
+    /// [`Repl::run`] if the terminal cannot be switched to raw mode.
+    pub fn run_interactive(&mut self, editor: &mut LineEditor) -> io::Result<()> {
+        let mut input = io::stdin().lock();
+        let mut out = io::stdout();
+        let mut err = io::stderr();
+
+        let Ok(_raw_mode) = RawMode::enable() else {
+            return self.run(input, &mut out, &mut err);
+        };
+        loop {
+            let completions = self.completions();
+            let line = editor.read_line(self.prompt(), &completions, &mut input, &mut err)?;
+            match line {
+                Input::Line(line) => {
+                    editor.add_history(&line)?;
+                    if !self.feed(&line, &mut out, &mut err)? {
+                        return Ok(());
+                    }
+                }
+                Input::Interrupted => self.pending.clear(),
+                Input::Eof => return Ok(()),
+            }
+        }
+    }
+
+    /// Words offered for Tab completion: keywords and defined variables.
+    pub fn completions(&self) -> Vec<String> {
+        let environment = &self.interpreter.environment;
+        let mut words: Vec<String> = Tokenizer::default()
+            .keywords
+            .keys()
+            .map(|keyword| keyword.to_string())
+            .chain(
+                environment
+                    .map
+                    .keys()
+                    .chain(environment.enclosing.keys())
+                    .map(|name| name.to_string()),
+            )
+            .collect();
+        words.sort();
+        words.dedup();
+        words
+    }
+
+    /// Handles one line of input. Returns `false` once the session should end.
+    pub fn feed(
+        &mut self,
+        line: &str,
+        out: &mut impl Write,
+        err: &mut impl Write,
+    ) -> io::Result<bool> {
+        if self.pending.is_empty() {
+            if let Some(command) = line.trim().strip_prefix(':') {
+                return self.command(command, out, err);
+            }
+        }
+
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        if !is_complete(&self.pending) {
+            return Ok(true);
+        }
+
+        let source = std::mem::take(&mut self.pending);
+        self.eval(&source, REPL_FILE, true, out, err)?;
+        Ok(true)
+    }
+
+    fn command(
+        &mut self,
+        command: &str,
+        out: &mut impl Write,
+        err: &mut impl Write,
+    ) -> io::Result<bool> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match (name, argument) {
+            ("quit", "") => return Ok(false),
+            ("reset", "") => self.interpreter = new_interpreter(&self.options),
+            ("env", "") => {
+                // Variables assigned without `var` live in `enclosing`, unless
+                // a `var` of the same name shadows them.
+                let environment = &self.interpreter.environment;
+                let mut variables: Vec<(String, String)> = environment
+                    .map
+                    .iter()
+                    .chain(
+                        environment
+                            .enclosing
+                            .iter()
+                            .filter(|(name, _)| !environment.map.contains_key(*name)),
+                    )
+                    .map(|(name, value)| (name.to_string(), self.interpreter.stringify(value)))
+                    .collect();
+                variables.sort();
+                for (name, value) in variables {
+                    writeln!(out, "{} = {}", name, value)?;
+                }
+            }
+            ("load", "") => writeln!(err, "Usage: :load <file>")?,
+            ("load", path) => match fs::read_to_string(path) {
+                Ok(source) => self.eval(&source, path, false, out, err)?,
+                Err(error) => writeln!(err, "Failed to read file {}: {}", path, error)?,
+            },
+            _ => writeln!(
+                err,
+                "Unknown command: :{} (expected :load, :env, :reset or :quit)",
+                command
+            )?,
+        }
+        Ok(true)
+    }
+
+    /// Runs `source`, printing the value of each expression statement when
+    /// `echo` is set.
+    fn eval(
+        &mut self,
+        source: &str,
+        file_name: &str,
+        echo: bool,
+        out: &mut impl Write,
+        err: &mut impl Write,
+    ) -> io::Result<()> {
+        let color = self.color.enabled();
+        let mut tokenizer = Tokenizer {
+            source,
+            ..Default::default()
+        };
+        if let Err(errors) = tokenizer.scan_tokens() {
+            for error in errors {
+                write!(
+                    err,
+                    "{}",
+                    error.to_diagnostic().render(file_name, source, color)
+                )?;
+            }
+            return Ok(());
+        }
+
+        let mut parser = Parser {
+            tokens: tokenizer.tokens,
+            max_depth: self.options.max_parse_depth,
+            ..Default::default()
+        };
+        let statements = match parser.parse() {
+            Ok(statements) => statements,
+            Err(errors) => {
+                for error in errors {
+                    write!(
+                        err,
+                        "{}",
+                        error.to_diagnostic().render(file_name, source, color)
+                    )?;
+                }
+                return Ok(());
+            }
+        };
+
+        match self.interpreter.interpret(&parser.ast, &statements) {
+            Ok(values) => {
+                if echo {
+                    for (statement, value) in statements.iter().zip(&values) {
+                        if let Statement::Expression(_) = parser.ast[*statement] {
+                            writeln!(out, "{}", self.interpreter.stringify(value))?;
+                        }
+                    }
+                }
+            }
+            Err(error) => write!(err, "{}", error.traceback(file_name, source))?,
+        }
+        Ok(())
+    }
+}
+
+fn new_interpreter(options: &Options) -> Interpreter {
+    let mut interpreter = Interpreter::default();
+    interpreter.max_depth = options.max_eval_depth;
+    interpreter.sandbox = options.sandbox.clone();
+    interpreter.decimal_context = options.decimal_context;
+    interpreter
+}
+
+/// Whether `source` can be run as is, rather than waiting for more lines to
+/// close an open string, parenthesis or brace.
+pub fn is_complete(source: &str) -> bool {
+    let mut tokenizer = Tokenizer {
+        source,
+        ..Default::default()
+    };
+    if let Err(errors) = tokenizer.scan_tokens() {
+        if errors
+            .iter()
+            .any(|error| matches!(error, TokenizeError::UnterminatedString { .. }))
+        {
+            return false;
+        }
+    }
+
+    let mut depth: isize = 0;
+    for token in &tokenizer.tokens {
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBrace => depth = depth.saturating_add(1),
+            TokenType::RightParen | TokenType::RightBrace => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    return depth <= 0;
+}
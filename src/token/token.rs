@@ -1,10 +1,17 @@
+use crate::bigint::BigInt;
+use crate::decimal::Decimal;
+use crate::interner::Symbol;
+use crate::span::Span;
 use crate::token::token_type;
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
-    Identifier(String),
-    Str(String),
+    Identifier(Symbol),
+    Str(Symbol),
+    Integer(i64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Number(f64),
 }
 
@@ -13,6 +20,10 @@ impl fmt::Display for Literal {
         let message = match self {
             Literal::Identifier(id) => id.to_string(),
             Literal::Str(str) => str.to_string(),
+            // Printed like whole floats, as `tokenize` always has.
+            Literal::Integer(n) => format!("{}.0", n),
+            Literal::BigInt(n) => n.to_string(),
+            Literal::Decimal(n) => n.to_string(),
             Literal::Number(n) => {
                 if n.fract() == 0.0 {
                     format!("{}.0", n)
@@ -25,12 +36,12 @@ impl fmt::Display for Literal {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub token_type: token_type::TokenType,
-    pub lexeme: String,
+    pub lexeme: Symbol,
     pub literal: Option<Literal>,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl fmt::Display for Token {
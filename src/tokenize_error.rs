@@ -0,0 +1,63 @@
+use thiserror::Error;
+
+use crate::diagnostic::Diagnostic;
+use crate::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TokenizeError {
+    #[error("Unexpected character: {character}")]
+    UnexpectedCharacter { character: char, span: Span },
+    #[error("Unterminated string.")]
+    UnterminatedString { span: Span },
+    #[error("Integer literal is too large.")]
+    IntegerTooLarge { span: Span },
+    #[error("BigInt literals cannot have a fractional part.")]
+    FractionalBigInt { span: Span },
+    #[error("Invalid number literal.")]
+    InvalidNumber { span: Span },
+}
+
+impl TokenizeError {
+    /// Stable identifier of this kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnexpectedCharacter { .. } => "E0001",
+            Self::UnterminatedString { .. } => "E0002",
+            Self::IntegerTooLarge { .. } => "E0003",
+            Self::FractionalBigInt { .. } => "E0004",
+            Self::InvalidNumber { .. } => "E0005",
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnexpectedCharacter { span, .. }
+            | Self::UnterminatedString { span }
+            | Self::IntegerTooLarge { span }
+            | Self::FractionalBigInt { span }
+            | Self::InvalidNumber { span } => *span,
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (label, help) = match self {
+            Self::UnterminatedString { .. } => (
+                Some(String::from("string starts here")),
+                vec![String::from("add a closing `\"` to end the string")],
+            ),
+            Self::FractionalBigInt { .. } => (
+                None,
+                vec![String::from("use the `d` suffix for an exact decimal")],
+            ),
+            _ => (None, vec![]),
+        };
+        Diagnostic {
+            code: Some(self.code()),
+            message: self.to_string(),
+            span: self.span(),
+            label,
+            help,
+            ..Default::default()
+        }
+    }
+}
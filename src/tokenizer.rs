@@ -1,16 +1,38 @@
 use std::collections::HashMap;
 
+use crate::bigint::BigInt;
+use crate::cst::{self, Trivia};
+use crate::decimal::Decimal;
+use crate::interner::Symbol;
+use crate::span::Span;
 use crate::token::token::Literal;
 use crate::token::token::Token;
 use crate::token::token_type::TokenType;
+use crate::tokenize_error::TokenizeError;
+use crate::unicode::{is_xid_continue, is_xid_start};
 
 pub struct Tokenizer<'a> {
     pub source: &'a str,
     pub line: usize,
+    /// Byte offset of the next character to scan.
     pub current: usize,
+    /// Byte offset of the first character of the token being scanned.
     pub start: usize,
+    /// Byte offset of the start of the line being scanned.
+    pub line_start: usize,
+    /// An offset on the current line and its column, so columns are counted
+    /// from the last token rather than from the start of the line.
+    pub column_mark: (usize, usize),
     pub tokens: Vec<Token>,
-    pub exit_code: u8,
+    /// Errors found so far; scanning carries on after each of them.
+    pub errors: Vec<TokenizeError>,
+    /// Spans of the `//` comments skipped while scanning, in source order.
+    pub comments: Vec<Span>,
+    /// Whether to record the whitespace and comments before each token in
+    /// `trivia`.
+    pub keep_trivia: bool,
+    /// Leading trivia of each token, indexed like `tokens`.
+    pub trivia: Vec<Vec<Trivia>>,
     pub keywords: HashMap<&'a str, TokenType>,
 }
 
@@ -21,8 +43,13 @@ impl<'a> Default for Tokenizer<'a> {
             line: 1,
             current: 0,
             start: 0,
+            line_start: 0,
+            column_mark: (0, 1),
             tokens: Vec::new(),
-            exit_code: 0,
+            errors: Vec::new(),
+            comments: Vec::new(),
+            keep_trivia: false,
+            trivia: Vec::new(),
             keywords: HashMap::from([
                 ("and", TokenType::And),
                 ("class", TokenType::Class),
@@ -47,80 +74,151 @@ impl<'a> Default for Tokenizer<'a> {
 
 impl<'a> Tokenizer<'a> {
     fn is_at_end(&self) -> bool {
-        return self.current >= self.source.chars().count();
+        return self.current >= self.source.len();
     }
 
-    pub fn scan_tokens(&mut self) -> u8 {
+    /// Scans the whole source. On failure the token list is still complete
+    /// apart from the offending lexemes, and every error found is returned.
+    pub fn scan_tokens(&mut self) -> Result<(), Vec<TokenizeError>> {
         while !self.is_at_end() {
             // We are at the beginning of the next lexeme.
             self.start = self.current;
             self.scan_token();
         }
 
+        self.start = self.source.len();
+        self.record_trivia();
+        let column = self.column(self.source.len());
         self.tokens.push(Token {
             token_type: TokenType::EOF,
-            lexeme: "".to_string(),
+            lexeme: Symbol::intern(""),
             literal: None,
-            line: self.line,
+            span: Span {
+                start: self.source.len(),
+                end: self.source.len(),
+                line: self.line,
+                column,
+            },
         });
 
-        return self.exit_code;
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        return Err(self.errors.clone());
     }
 
     fn match_expected(&mut self, expected: char) -> bool {
         if self.is_at_end() {
             return false;
         };
-        if self.source.chars().nth(self.current) != Some(expected) {
+        if self.peek() != Some(expected) {
             return false;
         };
 
-        self.current += 1;
+        self.current = self.current.saturating_add(expected.len_utf8());
         return true;
     }
 
     fn advance(&mut self) -> Option<char> {
-        let char = self.source.chars().nth(self.current);
-        self.current += 1;
+        let char = self.peek();
+        self.current = self.current.saturating_add(char.map_or(1, char::len_utf8));
         return char;
     }
 
     fn add_token(&mut self, token_type: TokenType, literal: Option<Literal>) {
-        let lexeme = self
-            .source
-            .get(self.start..self.current)
-            .unwrap()
-            .to_string();
+        let lexeme = Symbol::intern(self.source.get(self.start..self.current).unwrap());
+        self.record_trivia();
+        let span = self.span();
         self.tokens.push(Token {
             token_type,
             lexeme,
             literal,
-            line: self.line,
+            span,
         });
     }
 
-    fn peek(&self) -> Option<char> {
-        if self.is_at_end() {
-            return None;
+    /// Records the source between the previous token and the one starting
+    /// at `start` as its leading trivia.
+    fn record_trivia(&mut self) {
+        if !self.keep_trivia {
+            return;
+        }
+        let previous_end = self.tokens.last().map_or(0, |token| token.span.end);
+        let newlines = self.source[previous_end..self.current]
+            .matches('\n')
+            .count();
+        let column = self.column(previous_end);
+        self.trivia.push(cst::split_trivia(
+            self.source,
+            previous_end,
+            self.start,
+            self.line.saturating_sub(newlines),
+            column,
+        ));
+    }
+
+    /// Span of the token being scanned.
+    fn span(&mut self) -> Span {
+        // Multi-line strings have already advanced `line` past their start.
+        let newlines = self.source[self.start..self.current].matches('\n').count();
+        return Span {
+            start: self.start,
+            end: self.current,
+            line: self.line.saturating_sub(newlines),
+            column: self.column(self.start),
         };
-        return self.source.chars().nth(self.current);
     }
 
-    fn error(&mut self, message: String) {
-        eprintln!("[line {}] Error: {}", self.line, message);
-        self.exit_code = 65;
+    /// The 1-based column of the byte `offset`, counted in characters.
+    ///
+    /// Offsets are asked for in increasing order, so this only counts the
+    /// characters since the previous one.
+    fn column(&mut self, offset: usize) -> usize {
+        if offset < self.line_start {
+            // The start of a multi-line string, or the end of the token
+            // before it.
+            let line_start = self.source[..offset]
+                .rfind('\n')
+                .map_or(0, |i| i.saturating_add(1));
+            return self.source[line_start..offset]
+                .chars()
+                .count()
+                .saturating_add(1);
+        }
+
+        let (mut mark, mut column) = self.column_mark;
+        if mark < self.line_start || mark > offset {
+            (mark, column) = (self.line_start, 1);
+        }
+        column = column.saturating_add(self.source[mark..offset].chars().count());
+        self.column_mark = (offset, column);
+        return column;
+    }
+
+    /// Counts the newline just consumed.
+    fn newline(&mut self) {
+        self.line = self.line.saturating_add(1);
+        self.line_start = self.current;
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.source[self.current..].chars().next();
+    }
+
+    fn error(&mut self, error: impl FnOnce(Span) -> TokenizeError) {
+        let span = self.span();
+        self.errors.push(error(span));
     }
 
     fn string(&mut self) {
         while self.peek() != Some('"') && !self.is_at_end() {
-            if self.peek() == Some('\n') {
-                self.line += 1;
+            if self.advance() == Some('\n') {
+                self.newline();
             }
-            self.advance();
         }
 
         if self.is_at_end() {
-            self.error(String::from("Unterminated string."));
+            self.error(|span| TokenizeError::UnterminatedString { span });
             return;
         }
 
@@ -128,11 +226,13 @@ impl<'a> Tokenizer<'a> {
         self.advance();
 
         // Trim the surrounding quotes.
-        let value = self
-            .source
-            .get(self.start + 1..self.current - 1)
-            .unwrap()
-            .to_string();
+        let quoted = self.source.get(self.start..self.current).unwrap();
+        let value = Symbol::intern(
+            quoted
+                .strip_prefix('"')
+                .and_then(|quoted| quoted.strip_suffix('"'))
+                .unwrap(),
+        );
         self.add_token(TokenType::String, Some(Literal::Str(value)));
     }
 
@@ -141,47 +241,118 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn peek_next(&self) -> Option<char> {
-        if self.current + 1 >= self.source.chars().count() {
-            return None;
-        };
-        return self.source.chars().nth(self.current + 1);
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        return chars.next();
+    }
+
+    /// Consumes digits in the given radix, allowing single `_` separators
+    /// between them.
+    fn digits(&mut self, radix: u32) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_digit(radix) => {}
+                Some('_') if self.peek_next().is_some_and(|c| c.is_digit(radix)) => {}
+                _ => return,
+            }
+            self.advance();
+        }
     }
 
     fn number(&mut self) {
-        while self.is_digit(self.peek()) {
+        let radix = match (self.source[self.start..].chars().next(), self.peek()) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+
+        if radix != 10 && self.peek_next().is_some_and(|c| c.is_digit(radix)) {
+            // Consume the "x" or "b" of the prefix.
             self.advance();
+            self.digits(radix);
+
+            let digits = self.source[self.start..self.current][2..].replace('_', "");
+            if self.match_suffix('n') {
+                self.big_integer(&digits, radix);
+            } else {
+                self.integer(&digits, radix);
+            }
+            return;
         }
 
+        self.digits(10);
+
         // Look for a fractional part.
+        let mut is_fractional = false;
         if self.peek() == Some('.') && self.is_digit(self.peek_next()) {
             // Consume the "."
             self.advance();
+            self.digits(10);
+            is_fractional = true;
+        }
 
-            while self.is_digit(self.peek()) {
-                self.advance();
+        let text = self.source[self.start..self.current].replace('_', "");
+        if self.match_suffix('d') {
+            match Decimal::parse(&text) {
+                Some(decimal) => self.add_token(TokenType::Number, Some(Literal::Decimal(decimal))),
+                None => self.error(|span| TokenizeError::InvalidNumber { span }),
+            }
+        } else if self.match_suffix('n') {
+            if is_fractional {
+                self.error(|span| TokenizeError::FractionalBigInt { span });
+            } else {
+                self.big_integer(&text, 10);
             }
+        } else if is_fractional {
+            self.add_token(
+                TokenType::Number,
+                Some(Literal::Number(text.parse::<f64>().unwrap())),
+            );
+        } else if text.parse::<i64>().is_err() {
+            // Too large for an integer: a float, as before integers existed.
+            self.add_token(
+                TokenType::Number,
+                Some(Literal::Number(text.parse::<f64>().unwrap())),
+            );
+        } else {
+            self.integer(&text, 10);
         }
+    }
 
-        self.add_token(
-            TokenType::Number,
-            Some(Literal::Number(
-                self.source
-                    .get(self.start..self.current)
-                    .unwrap()
-                    .parse::<f64>()
-                    .unwrap(),
-            )),
-        );
+    /// Consumes a one letter literal suffix such as the `n` in `10n`, unless
+    /// it is the start of a longer identifier.
+    fn match_suffix(&mut self, suffix: char) -> bool {
+        if self.peek() == Some(suffix) && !self.is_alpha_numeric(self.peek_next()) {
+            self.advance();
+            return true;
+        }
+        return false;
     }
 
+    fn big_integer(&mut self, digits: &str, radix: u32) {
+        match BigInt::parse(digits, radix) {
+            Some(n) => self.add_token(TokenType::Number, Some(Literal::BigInt(n))),
+            None => self.error(|span| TokenizeError::InvalidNumber { span }),
+        }
+    }
+
+    fn integer(&mut self, digits: &str, radix: u32) {
+        match i64::from_str_radix(digits, radix) {
+            Ok(n) => self.add_token(TokenType::Number, Some(Literal::Integer(n))),
+            Err(_) => self.error(|span| TokenizeError::IntegerTooLarge { span }),
+        }
+    }
+
+    /// Whether `c` can start an identifier: an underscore or a Unicode
+    /// `XID_Start` character.
     fn is_alpha(&self, c: Option<char>) -> bool {
-        return (c >= Some('a') && c <= Some('z'))
-            || (c >= Some('A') && c <= Some('Z'))
-            || c == Some('_');
+        return c.is_some_and(|c| c == '_' || is_xid_start(c));
     }
 
+    /// Whether `c` can continue an identifier: a Unicode `XID_Continue`
+    /// character, which includes digits and underscores.
     fn is_alpha_numeric(&self, c: Option<char>) -> bool {
-        return self.is_alpha(c) || self.is_digit(c);
+        return c.is_some_and(is_xid_continue);
     }
 
     fn identifier(&mut self) {
@@ -244,6 +415,8 @@ impl<'a> Tokenizer<'a> {
                     while self.peek() != Some('\n') && !self.is_at_end() {
                         self.advance();
                     }
+                    let span = self.span();
+                    self.comments.push(span);
                 } else {
                     self.add_token(TokenType::Slash, None);
                 }
@@ -251,9 +424,7 @@ impl<'a> Tokenizer<'a> {
             Some(' ') => {}
             Some('\r') => {}
             Some('\t') => {}
-            Some('\n') => {
-                self.line += 1;
-            }
+            Some('\n') => self.newline(),
             Some('"') => self.string(),
             Some(other) => {
                 if self.is_digit(Some(other)) {
@@ -261,7 +432,10 @@ impl<'a> Tokenizer<'a> {
                 } else if self.is_alpha(Some(other)) {
                     self.identifier();
                 } else {
-                    self.error(format!("Unexpected character: {}", other));
+                    self.error(|span| TokenizeError::UnexpectedCharacter {
+                        character: other,
+                        span,
+                    });
                 }
             }
             None => todo!(
@@ -0,0 +1,378 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn write_source(name: &str, source: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("loxc-{}-{}.lox", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+fn run(name: &str, args: &[&str], source: &str) -> Output {
+    let path = write_source(name, source);
+    let output = Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(path).unwrap();
+    output
+}
+
+#[test]
+fn gc_stress_keeps_reachable_strings_alive() {
+    let output = run(
+        "gc-stress",
+        &["interpret", "--gc-stress", "--gc-stats"],
+        "var s = \"\";
+         var i = 0;
+         while (i < 10) { s = s + \"a\"; i = i + 1; }
+         print s;
+         print (\"a\" + \"b\") + (\"c\" + \"d\");",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        "\"aaaaaaaaaa\"\n\"abcd\"\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let stats = String::from_utf8_lossy(&output.stderr);
+    assert!(stats.contains("[gc] collections: 13"), "{}", stats);
+    assert!(
+        stats.contains("objects allocated: 13, freed: 11"),
+        "{}",
+        stats
+    );
+}
+
+#[test]
+fn optimizer_keeps_program_behaviour() {
+    let output = run(
+        "optimize",
+        &["interpret", "-O"],
+        "var a = (1 + 2) * 3 - -1;
+         if (1 < 2 and true) print a; else print 1 / 0;
+         while (false) print 1 / 0;
+         if (nil) { print \"never\"; }
+         print \"foo\" + \"bar\" == \"foobar\";",
+    );
+
+    assert!(output.status.success());
+    assert_eq!("10\ntrue\n", String::from_utf8_lossy(&output.stdout));
+
+    // Concatenating at compile time would intern the result for good.
+    let output = run(
+        "optimize-strings",
+        &["parse", "-O"],
+        "print \"a\" + \"b\"; print 1 + 2;",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        "(print (+ \"a\" \"b\"))\n(print 3.0)\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn optimizer_preserves_runtime_errors() {
+    let output = run("optimize-error", &["interpret", "-O"], "print 1 / (2 - 2);");
+
+    assert_eq!(Some(70), output.status.code());
+    assert_eq!("", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn max_depth_option_limits_nesting() {
+    let output = run(
+        "max-depth",
+        &["interpret", "--max-depth=3"],
+        "print ((((1))));",
+    );
+
+    assert_eq!(Some(65), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Too deeply nested"));
+}
+
+#[test]
+fn runtime_errors_print_a_traceback() {
+    let output = run(
+        "runtime-error-line",
+        &["interpret"],
+        "var a = 1;\nprint a;\nprint a *\n  \"b\";",
+    );
+
+    assert_eq!(Some(70), output.status.code());
+    assert_eq!("1\n", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Traceback (most recent call last):\n  File \""));
+    assert!(stderr.ends_with(
+        ".lox\", line 3, in <script>\n    print a *\nRuntimeError: Operands must be numbers.\n"
+    ));
+}
+
+#[test]
+fn errors_in_variable_initializers_are_reported() {
+    let output = run("var-initializer-error", &["interpret"], "var a = -\"x\";");
+
+    assert_eq!(Some(70), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).ends_with(
+        "line 1, in <script>\n    var a = -\"x\";\nRuntimeError: Operand must be a number.\n"
+    ));
+}
+
+#[test]
+fn reports_tokenizer_errors_with_snippets() {
+    let output = run("tokenize-error", &["tokenize", "--color=never"], "print $;");
+
+    assert_eq!(Some(65), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("error[E0001]: Unexpected character: $\n --> "));
+    assert!(stderr.ends_with(":1:7\n  |\n1 | print $;\n  |       ^\n\n"));
+}
+
+#[test]
+fn reports_parse_errors_with_snippets() {
+    let output = run("parse-error", &["parse", "--color=always"], "print (1;");
+
+    assert_eq!(Some(65), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Expect ')' after expression."));
+    assert!(stderr.contains("\x1b[1;31m^ found `;`"));
+}
+
+#[test]
+fn reports_every_syntax_error() {
+    let output = run(
+        "parse-recovery",
+        &["parse"],
+        "print 1 +;\nvar = 2;\n{ print (3; }\nprint 4;\n",
+    );
+
+    assert_eq!(Some(65), output.status.code());
+    assert_eq!("", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let locations: Vec<&str> = stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix(" --> "))
+        .map(|location| &location[location.find(".lox:").unwrap() + 5..])
+        .collect();
+    assert_eq!(vec!["1:10", "2:5", "3:11"], locations);
+}
+
+#[test]
+fn parse_errors_name_what_was_expected_and_where() {
+    let output = run(
+        "parse-error-context",
+        &["parse"],
+        "for (var i = 0; i < 3 i = i + 1) print i;\n1 + 2 = 3;\n",
+    );
+
+    assert_eq!(Some(65), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("error[E0101]: Expect ';' after loop condition.\n"));
+    assert!(stderr.contains("^ found `i` in for clauses\n"));
+    assert!(stderr.contains("error[E0103]: Invalid assignment target.\n"));
+    assert!(stderr.contains("\n2 | 1 + 2 = 3;\n  | ^^^^^ cannot assign to this\n"));
+}
+
+#[test]
+fn runs_a_repl_on_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"var a = 20;\nprint a;\na + 1;\n:quit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!("20\n21\n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn prints_the_syntax_tree_as_s_expressions() {
+    let output = run(
+        "parse-tree",
+        &["parse"],
+        "var a = 1;
+         var b;
+         a = !true or b and -a;
+         if (a >= 2) print \"big\"; else { print \"small\"; b = nil; }
+         for (var i = 0; i < 3; i = i + 1) print (i * 2.5) / 1n - 0.5d;
+         while (false) {}",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        "(var a 1.0)
+(var b)
+(expr (= a (or (! true) (and b (- a)))))
+(if (>= a 2.0) (print \"big\") (block (print \"small\") (expr (= b nil))))
+(block (var i 0.0) (while (< i 3.0) (block (print (- (/ (group (* i 2.5)) 1n) 0.5d)) (expr (= i (+ i 1.0))))))
+(while false (block))
+",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn exports_tokens_as_json() {
+    let output = run(
+        "tokenize-json",
+        &["tokenize", "--format=json"],
+        "x = \"a\nb\";",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        "{\"tokens\":[\
+         {\"type\":\"IDENTIFIER\",\"lexeme\":\"x\",\"literal\":null,\"span\":{\"start\":0,\"end\":1,\"line\":1,\"column\":1}},\
+         {\"type\":\"EQUAL\",\"lexeme\":\"=\",\"literal\":null,\"span\":{\"start\":2,\"end\":3,\"line\":1,\"column\":3}},\
+         {\"type\":\"STRING\",\"lexeme\":\"\\\"a\\nb\\\"\",\"literal\":{\"type\":\"string\",\"value\":\"a\\nb\"},\"span\":{\"start\":4,\"end\":9,\"line\":1,\"column\":5}},\
+         {\"type\":\"SEMICOLON\",\"lexeme\":\";\",\"literal\":null,\"span\":{\"start\":9,\"end\":10,\"line\":2,\"column\":3}},\
+         {\"type\":\"EOF\",\"lexeme\":\"\",\"literal\":null,\"span\":{\"start\":10,\"end\":10,\"line\":2,\"column\":4}}\
+         ],\"errors\":[]}\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn exports_the_syntax_tree_as_json() {
+    let output = run(
+        "parse-json",
+        &["parse", "--format=json"],
+        "if (a) -1; else b = nil;",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        "{\"statements\":[{\"kind\":\"If\",\
+         \"condition\":{\"kind\":\"Variable\",\"name\":\"a\",\"span\":{\"start\":4,\"end\":5,\"line\":1,\"column\":5}},\
+         \"then_branch\":{\"kind\":\"Expression\",\"expression\":{\"kind\":\"Unary\",\"operator\":\"-\",\
+         \"operand\":{\"kind\":\"Literal\",\"value\":{\"type\":\"integer\",\"value\":1},\"span\":{\"start\":8,\"end\":9,\"line\":1,\"column\":9}},\
+         \"span\":{\"start\":7,\"end\":9,\"line\":1,\"column\":8}},\"span\":{\"start\":7,\"end\":10,\"line\":1,\"column\":8}},\
+         \"else_branch\":{\"kind\":\"Expression\",\"expression\":{\"kind\":\"Assign\",\"name\":\"b\",\
+         \"value\":{\"kind\":\"Literal\",\"value\":{\"type\":\"nil\",\"value\":null},\"span\":{\"start\":20,\"end\":23,\"line\":1,\"column\":21}},\
+         \"span\":{\"start\":16,\"end\":23,\"line\":1,\"column\":17}},\"span\":{\"start\":16,\"end\":24,\"line\":1,\"column\":17}},\
+         \"span\":{\"start\":0,\"end\":24,\"line\":1,\"column\":1}}],\"errors\":[]}\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn exports_errors_as_json() {
+    let output = run(
+        "parse-json-error",
+        &["parse", "--format=json", "--color=never"],
+        "print (;",
+    );
+
+    assert_eq!(Some(65), output.status.code());
+    assert_eq!(
+        "{\"statements\":[],\"errors\":[{\"code\":\"E0102\",\"message\":\"Expect expression.\",\
+         \"span\":{\"start\":7,\"end\":8,\"line\":1,\"column\":8}}]}\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn formats_files_in_place_or_checks_them() {
+    let messy = write_source("fmt-messy", "print 1+2;");
+    let tidy = write_source("fmt-tidy", "print 1;\n");
+
+    let check = Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .args(["fmt", "--check"])
+        .args([&messy, &tidy])
+        .output()
+        .unwrap();
+    assert_eq!(Some(1), check.status.code());
+    let stderr = String::from_utf8_lossy(&check.stderr);
+    assert!(stderr.contains(&format!("Would reformat {}", messy.display())));
+    assert!(!stderr.contains(&tidy.display().to_string()));
+    assert_eq!("print 1+2;", fs::read_to_string(&messy).unwrap());
+
+    let format = Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .arg("fmt")
+        .args([&messy, &tidy])
+        .output()
+        .unwrap();
+    assert!(format.status.success());
+    assert_eq!("print 1 + 2;\n", fs::read_to_string(&messy).unwrap());
+    assert_eq!("print 1;\n", fs::read_to_string(&tidy).unwrap());
+
+    fs::remove_file(messy).unwrap();
+    fs::remove_file(tidy).unwrap();
+}
+
+#[test]
+fn lints_files_with_configurable_rules() {
+    let source = "var a = 1;\na = a;\nvar unused;\n";
+    let output = run("lint", &["lint", "--color=never"], source);
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("warning[self-assignment]: `a` is assigned to itself."));
+    assert!(stderr.contains("warning[unused-variable]: Unused variable `unused`."));
+
+    let output = run(
+        "lint-disabled",
+        &["lint", "--disable=all", "--enable=unused-variable"],
+        "var a = 1;\na = a;\n",
+    );
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let output = run("lint-unknown", &["lint", "--disable=typo"], source);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "Invalid value for --disable: typo\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = run("lint-invalid", &["lint"], "print (;");
+    assert_eq!(Some(65), output.status.code());
+}
+
+#[test]
+fn serves_the_language_server_protocol_on_stdio() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let input: String = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]
+    .iter()
+    .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+    .collect();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_loxc"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Content-Length: "));
+    assert!(stdout.contains(r#""capabilities":{"#));
+    assert!(stdout.ends_with(r#"{"jsonrpc":"2.0","id":2,"result":null}"#));
+}
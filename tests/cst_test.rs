@@ -0,0 +1,123 @@
+use loxc::cst::{parse, Element, Node, SyntaxKind, TriviaKind};
+use loxc::token::token_type::TokenType;
+use loxc::{execute, Error};
+
+fn kinds(node: &Node) -> Vec<SyntaxKind> {
+    let mut all = vec![node.kind];
+    for child in node.nodes() {
+        all.extend(kinds(child));
+    }
+    all
+}
+
+#[test]
+fn reproduces_the_source_byte_for_byte() {
+    let sources = [
+        "",
+        "   \n// only a comment",
+        "var a = 1; // one\n\n\t{ print  a +\r\n 2 ; }\n",
+        "for (var i = 0; i < 3; i = i + 1) print i;\nif (a) b = 1; else { while (c) c = !c; }\n",
+        "var ünïcode = \"snow ☃\"; // ☃\n",
+        "print (1 + ;\nvar = 2;\nprint 3;\n",
+        "var a = 1 @ 2; # print \"never closed\n",
+    ];
+    for source in sources {
+        assert_eq!(source, parse(source).root.to_string());
+    }
+}
+
+#[test]
+fn builds_nodes_for_statements_and_expressions() {
+    let tree = parse("var a = 1;\nprint -a * (2 + a);\n");
+    assert!(tree.error.is_none());
+    assert_eq!(
+        vec![
+            SyntaxKind::Program,
+            SyntaxKind::VarDeclaration,
+            SyntaxKind::LiteralExpr,
+            SyntaxKind::PrintStatement,
+            SyntaxKind::BinaryExpr,
+            SyntaxKind::UnaryExpr,
+            SyntaxKind::VariableExpr,
+            SyntaxKind::GroupingExpr,
+            SyntaxKind::BinaryExpr,
+            SyntaxKind::LiteralExpr,
+            SyntaxKind::VariableExpr,
+        ],
+        kinds(&tree.root)
+    );
+
+    let print = tree.root.nodes().nth(1).unwrap();
+    assert_eq!("\nprint -a * (2 + a);", print.to_string());
+}
+
+#[test]
+fn attaches_trivia_to_the_following_token() {
+    let tree = parse("var a; // note\n  print a;");
+    let tokens = tree.root.tokens();
+    let print = tokens[3];
+    assert_eq!(TokenType::Print, print.token_type);
+    let kinds: Vec<TriviaKind> = print.leading_trivia.iter().map(|t| t.kind).collect();
+    assert_eq!(
+        vec![
+            TriviaKind::Whitespace,
+            TriviaKind::Comment,
+            TriviaKind::Newline,
+            TriviaKind::Whitespace
+        ],
+        kinds
+    );
+    let comment = &print.leading_trivia[1];
+    assert_eq!("// note", comment.text);
+    assert_eq!((1, 8), (comment.span.line, comment.span.column));
+    assert_eq!(2, print.span.line);
+
+    let eof = tokens.last().unwrap();
+    assert_eq!(TokenType::EOF, eof.token_type);
+    assert!(eof.leading_trivia.is_empty());
+}
+
+#[test]
+fn keeps_invalid_source_in_error_nodes_and_skipped_trivia() {
+    let tree = parse("print (;\nprint 1;");
+    assert!(matches!(tree.error, Some(Error::ParseError(_))));
+    let nodes: Vec<SyntaxKind> = tree.root.nodes().map(|node| node.kind).collect();
+    assert_eq!(vec![SyntaxKind::Error, SyntaxKind::PrintStatement], nodes);
+
+    let tree = parse("print 1 $;");
+    assert!(matches!(tree.error, Some(Error::TokenizingError(_))));
+    let skipped = tree
+        .root
+        .tokens()
+        .into_iter()
+        .flat_map(|token| &token.leading_trivia)
+        .find(|trivia| trivia.kind == TriviaKind::Skipped)
+        .unwrap();
+    assert_eq!("$", skipped.text);
+    assert!(matches!(
+        tree.root.children.last(),
+        Some(Element::Token(token)) if token.token_type == TokenType::EOF
+    ));
+}
+
+#[test]
+fn leaves_the_ast_unchanged() {
+    let source = "var a = 1; // one\nfor (var i = 0; i < 2; i = i + 1) a = a * 2;\nprint a;";
+    let tree = parse(source);
+    assert!(tree.error.is_none());
+    let for_statement = tree.root.nodes().nth(1).unwrap();
+    assert_eq!(SyntaxKind::ForStatement, for_statement.kind);
+    assert_eq!(
+        vec![
+            SyntaxKind::VarDeclaration,
+            SyntaxKind::BinaryExpr,
+            SyntaxKind::AssignExpr,
+            SyntaxKind::ExpressionStatement
+        ],
+        for_statement
+            .nodes()
+            .map(|node| node.kind)
+            .collect::<Vec<_>>()
+    );
+    assert!(execute(source).is_ok());
+}
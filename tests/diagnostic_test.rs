@@ -0,0 +1,93 @@
+use loxc::diagnostic::{Diagnostic, Severity};
+use loxc::span::Span;
+
+#[test]
+fn renders_a_snippet_with_carets_under_the_span() {
+    let source = "var a = 1;\nprint a +* 2;\n";
+    let diagnostic = Diagnostic {
+        code: Some("E0104"),
+        message: String::from("Unexpected token: STAR"),
+        span: Span {
+            start: 20,
+            end: 21,
+            line: 2,
+            column: 10,
+        },
+        label: Some(String::from("found `*`")),
+        help: vec![String::from("remove the extra operator")],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        "error[E0104]: Unexpected token: STAR
+ --> test.lox:2:10
+  |
+2 | print a +* 2;
+  |          ^ found `*`
+  |
+  = help: remove the extra operator
+
+",
+        diagnostic.render("test.lox", source, false)
+    );
+}
+
+#[test]
+fn counts_columns_in_characters_and_keeps_tabs() {
+    let source = "\t\"é\" + ☕;";
+    let diagnostic = Diagnostic {
+        message: String::from("Unexpected character: ☕"),
+        span: Span {
+            start: 9,
+            end: 12,
+            line: 1,
+            column: 7,
+        },
+        ..Default::default()
+    };
+
+    let rendered = diagnostic.render("test.lox", source, false);
+    assert!(rendered.contains("\n  | \t     ^\n"), "{}", rendered);
+}
+
+#[test]
+fn colors_only_when_asked() {
+    let diagnostic = Diagnostic {
+        message: String::from("Oops."),
+        span: Span {
+            start: 0,
+            end: 1,
+            line: 1,
+            column: 1,
+        },
+        ..Default::default()
+    };
+
+    assert!(diagnostic
+        .render("a.lox", "x", true)
+        .contains("\x1b[1;31merror"));
+    assert!(!diagnostic.render("a.lox", "x", false).contains('\x1b'));
+}
+
+#[test]
+fn renders_warnings() {
+    let diagnostic = Diagnostic {
+        severity: Severity::Warning,
+        code: Some("unused-variable"),
+        message: String::from("Unused variable `a`."),
+        span: Span {
+            start: 4,
+            end: 5,
+            line: 1,
+            column: 5,
+        },
+        ..Default::default()
+    };
+
+    assert!(diagnostic
+        .render("a.lox", "var a;", false)
+        .starts_with("warning[unused-variable]: Unused variable `a`.\n"));
+    assert!(diagnostic
+        .render("a.lox", "var a;", true)
+        .contains("\x1b[1;33m^"));
+}
@@ -0,0 +1,77 @@
+use loxc::formatter::format;
+use loxc::{execute, Error};
+
+#[test]
+fn formats_statements_canonically() {
+    let source = "var a=1;var b ;\n{print a+b*  (2-1);\n  if(a>=2)print \"big\";else if (a==1) { print \"one\"; }\n  else {b = nil;}}\nwhile (a < 10) a = a + 1;\nif (true) {} else print -0xff + 1.50d;\n";
+    assert_eq!(
+        "var a = 1;
+var b;
+{
+    print a + b * (2 - 1);
+    if (a >= 2)
+        print \"big\";
+    else if (a == 1) {
+        print \"one\";
+    } else {
+        b = nil;
+    }
+}
+while (a < 10)
+    a = a + 1;
+if (true) {} else
+    print -0xff + 1.50d;
+",
+        format(source).unwrap()
+    );
+}
+
+#[test]
+fn keeps_for_loops() {
+    assert_eq!(
+        "for (var i = 0; i < 3; i = i + 1) {\n    print i;\n}\nfor (;;)\n    print 1;\nfor (i = 0; i < 1;) {}\n",
+        format("for(var i=0;i<3;i=i+1){print i;}\nfor(;;) print 1;\nfor (i = 0; i < 1;) {}").unwrap()
+    );
+}
+
+#[test]
+fn keeps_comments_and_blank_lines() {
+    let source = "// Header\n\nvar a = 1;   // trailing\n{ // after brace\n// inside\nprint a;\n\n\n\nprint a; // last\n// before brace\n}\n// end\n";
+    assert_eq!(
+        "// Header\n\nvar a = 1; // trailing\n{ // after brace\n    // inside\n    print a;\n\n    print a; // last\n    // before brace\n}\n// end\n",
+        format(source).unwrap()
+    );
+}
+
+#[test]
+fn moves_comments_inside_statements_to_their_first_line() {
+    let source = "var x = 1 + // inner\n 2;\nprint x;\nif (x) print \"a\nb\" + // in body\n x;\nprint aaaaaaaaaaaaaaaaaaaa + // first\n bbbbbbbbbbbbbbbbbbbbbbbbbbbb + cccccccccccccccccccccccccc;\n";
+    let formatted = format(source).unwrap();
+    assert_eq!(
+        "var x = 1 + 2; // inner\nprint x;\nif (x)\n    print \"a\nb\" + x; // in body\nprint aaaaaaaaaaaaaaaaaaaa // first\n    + bbbbbbbbbbbbbbbbbbbbbbbbbbbb\n    + cccccccccccccccccccccccccc;\n",
+        formatted
+    );
+    assert_eq!(formatted, format(&formatted).unwrap());
+}
+
+#[test]
+fn wraps_long_expressions_before_operators() {
+    let source = "print aaaaaaaaaaaaaaaaaaaa + bbbbbbbbbbbbbbbbbbbbbbbbbbbb + cccccccccccccccccccccccccc or d;";
+    assert_eq!(
+        "print aaaaaaaaaaaaaaaaaaaa\n    + bbbbbbbbbbbbbbbbbbbbbbbbbbbb\n    + cccccccccccccccccccccccccc\n    or d;\n",
+        format(source).unwrap()
+    );
+}
+
+#[test]
+fn is_idempotent_and_keeps_meaning() {
+    let source = "var s = \"x\";var i=0;while(i<3){s=s+\"y\";i=i+1;}\nfor (var j = 0; j < 2; j = j + 1) s = s + \"z\"; s;";
+    let formatted = format(source).unwrap();
+    assert_eq!(formatted, format(&formatted).unwrap());
+    assert_eq!(execute(source).unwrap(), execute(&formatted).unwrap());
+}
+
+#[test]
+fn rejects_invalid_source() {
+    assert!(matches!(format("print (;"), Err(Error::ParseError(_))));
+}
@@ -1,4 +1,18 @@
-use loxc::{execute, Error};
+use loxc::decimal::{DecimalContext, Rounding};
+use loxc::interpreter;
+use loxc::parse_error::ParseError;
+use loxc::tokenize_error::TokenizeError;
+use loxc::{execute, execute_with_options, Error, Frame, Options};
+
+/// The runtime error `result` failed with and the line of its innermost frame.
+fn runtime_error(result: Result<Vec<String>, Error>) -> (String, usize) {
+    match result {
+        Err(Error::InterpreterError { error, frames, .. }) => {
+            (error.to_string(), frames.last().unwrap().line)
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
 
 #[test]
 fn empty_source() {
@@ -85,9 +99,323 @@ fn errors_when_dividing_by_zero() {
 #[test]
 fn errors_when_trying_to_add_number_to_a_string() {
     assert_eq!(
-        Some(Error::InterpreterError(
-            "Runtime error in binary expression!".to_string()
-        )),
-        execute("\"hello\" + 10;").err()
+        (String::from("Runtime error in binary expression!"), 1),
+        runtime_error(execute("\"hello\" + 10;"))
     );
 }
+
+#[test]
+fn handles_while_loops() {
+    let results = execute("var i = 0; while (i < 3) i = i + 1; i;").unwrap();
+    assert_eq!("3", results[2]);
+}
+
+#[test]
+fn compares_strings() {
+    let results =
+        execute("\"foo\" == \"foo\"; \"foo\" + \"bar\" == \"foobar\"; \"foo\" != \"bar\";")
+            .unwrap();
+    assert_eq!(vec!["true", "true", "true"], results);
+}
+
+#[test]
+fn compares_numbers_for_equality() {
+    let results = execute("1 == 1; 1 == 2; 1 != 2;").unwrap();
+    assert_eq!(vec!["true", "false", "true"], results);
+}
+
+#[test]
+fn reports_stack_overflow_instead_of_crashing() {
+    let sources = [
+        format!("{}1;", "-".repeat(600)),
+        format!("{}print 1;{}", "{ var a = 1; ".repeat(600), "}".repeat(600)),
+        format!("{}print 1;", "if (true) ".repeat(600)),
+    ];
+    for source in sources {
+        // Let the parser accept the source, so it is the interpreter that has
+        // to give up, on a thread with the default stack size.
+        let options = Options {
+            max_parse_depth: 10_000,
+            ..Default::default()
+        };
+        let result = std::thread::spawn(move || execute_with_options(&source, &options))
+            .join()
+            .unwrap();
+
+        assert_eq!(String::from("Stack overflow."), runtime_error(result).0);
+    }
+}
+
+#[test]
+fn rejects_deeply_nested_expressions_while_parsing() {
+    let parens = format!("{}1{};", "(".repeat(2000), ")".repeat(2000));
+    let error = execute(&parens).unwrap_err();
+    assert_eq!(
+        "Parse Error: Too deeply nested at line 1",
+        error.to_string()
+    );
+    assert!(matches!(
+        error,
+        Error::ParseError(errors) if matches!(errors[..], [ParseError::TooDeeplyNestedError { .. }])
+    ));
+
+    let negations = format!("{}1;", "-".repeat(2000));
+    assert!(matches!(execute(&negations), Err(Error::ParseError(_))));
+}
+
+#[test]
+fn counts_operator_chains_toward_the_nesting_depth() {
+    for operator in ["+", "*", "==", "<", "and", "or"] {
+        let chain = format!("{}1;", format!("1 {} ", operator).repeat(10_000));
+        let error = execute(&chain).unwrap_err();
+        assert!(
+            matches!(&error, Error::ParseError(errors) if matches!(errors[..], [ParseError::TooDeeplyNestedError { .. }])),
+            "{}: {}",
+            operator,
+            error
+        );
+    }
+
+    let options = Options {
+        max_parse_depth: 10,
+        ..Default::default()
+    };
+    assert!(execute_with_options("1 + 2 + 3 + 4 + 5;", &options).is_ok());
+    assert!(
+        execute_with_options("1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 9 + 10 + 11;", &options).is_err()
+    );
+}
+
+#[test]
+fn counts_assignment_chains_toward_the_nesting_depth() {
+    let chain = format!("var a; {}1;", "a = ".repeat(20_000));
+    assert!(matches!(
+        execute(&chain),
+        Err(Error::ParseError(errors)) if matches!(errors[..], [ParseError::TooDeeplyNestedError { .. }])
+    ));
+
+    let options = Options {
+        max_parse_depth: 10,
+        ..Default::default()
+    };
+    assert!(execute_with_options("var a; a = a = a = 1;", &options).is_ok());
+    let chain = format!("var a; {}1;", "a = ".repeat(11));
+    assert!(execute_with_options(&chain, &options).is_err());
+}
+
+#[test]
+fn reports_too_deep_nesting_once() {
+    let blocks = format!("{}{}", "{".repeat(1000), "}".repeat(1000));
+    let Err(Error::ParseError(errors)) = execute(&blocks) else {
+        panic!("expected a parse error");
+    };
+    assert!(matches!(
+        errors[..],
+        [ParseError::TooDeeplyNestedError { .. }]
+    ));
+}
+
+#[test]
+fn accepts_nesting_up_to_the_configured_depth() {
+    let options = Options {
+        max_parse_depth: 10,
+        max_eval_depth: 10,
+        ..Default::default()
+    };
+
+    assert!(execute_with_options("((((1))));", &options).is_ok());
+    assert!(execute_with_options("((((((((((1))))))))));", &options).is_err());
+    assert!(execute("((((((((((1))))))))));").is_ok());
+}
+
+#[test]
+fn keeps_integers_exact() {
+    let results =
+        execute("9007199254740993; 9007199254740992 + 1; 1000000000000000000000.0 > 1;").unwrap();
+    assert_eq!(
+        vec!["9007199254740993", "9007199254740993", "true"],
+        results
+    );
+}
+
+#[test]
+fn handles_integer_literal_forms() {
+    let results = execute("0xFF; 0b1010; 1_000_000; 0xdead_beef;").unwrap();
+    assert_eq!(vec!["255", "10", "1000000", "3735928559"], results);
+}
+
+#[test]
+fn promotes_integers_mixed_with_floats() {
+    let results = execute("1 + 0.5; 6 / 3; 7 / 2; 2 == 2.0; 3 > 2.5;").unwrap();
+    assert_eq!(vec!["1.5", "2", "3.5", "true", "true"], results);
+}
+
+#[test]
+fn errors_on_integer_overflow() {
+    assert!(matches!(
+        execute("9223372036854775807 + 1;"),
+        Err(Error::InterpreterError {
+            error: interpreter::Error::IntegerOverflowError,
+            ..
+        })
+    ));
+    assert!(execute("-9223372036854775807 - 2;").is_err());
+    assert!(execute("4294967296 * 4294967296;").is_err());
+}
+
+#[test]
+fn errors_on_integer_literals_that_do_not_fit() {
+    assert_eq!(
+        vec!["9223372036854776000"],
+        execute("9223372036854775808;").unwrap()
+    );
+
+    let error = execute("0x8000000000000000;").unwrap_err();
+    assert!(matches!(
+        &error,
+        Error::TokenizingError(errors) if matches!(errors[..], [TokenizeError::IntegerTooLarge { .. }])
+    ));
+    assert_eq!(Some("E0003"), error.code());
+    assert_eq!(65, error.exit_code());
+}
+
+#[test]
+fn computes_with_bigints() {
+    let results = execute(
+        "123456789012345678901234567890n * 10n; 9223372036854775807 + 1n; 0xffn; 10n / 5; 3n > 2;",
+    )
+    .unwrap();
+    assert_eq!(
+        vec![
+            "1234567890123456789012345678900",
+            "9223372036854775808",
+            "255",
+            "2",
+            "true"
+        ],
+        results
+    );
+}
+
+#[test]
+fn keeps_decimals_exact() {
+    let results = execute("0.1d + 0.2d == 0.3d; 1.50d; 1.5d * 2; 10n / 4n; -2d / 3d;").unwrap();
+    assert_eq!(
+        vec!["true", "1.50", "3.0", "2.5", "-0.66666666666666666667"],
+        results
+    );
+}
+
+#[test]
+fn rounds_decimal_division_with_the_configured_context() {
+    let with = |rounding| Options {
+        decimal_context: DecimalContext { scale: 2, rounding },
+        ..Default::default()
+    };
+
+    let source = "2d / 3d; 0.125d / 1; -0.125d / 1;";
+    assert_eq!(
+        vec!["0.67", "0.12", "-0.12"],
+        execute_with_options(source, &with(Rounding::HalfEven)).unwrap()
+    );
+    assert_eq!(
+        vec!["0.67", "0.13", "-0.13"],
+        execute_with_options(source, &with(Rounding::HalfUp)).unwrap()
+    );
+    assert_eq!(
+        vec!["0.66", "0.12", "-0.13"],
+        execute_with_options(source, &with(Rounding::Floor)).unwrap()
+    );
+}
+
+#[test]
+fn rounds_decimal_products_beyond_the_configured_scale() {
+    let options = Options {
+        decimal_context: DecimalContext {
+            scale: 2,
+            rounding: Rounding::HalfEven,
+        },
+        ..Default::default()
+    };
+
+    let source = "0.15d * 0.5d; 0.25d * 0.5d; 0.125d * 2; 1.5d * 2;";
+    assert_eq!(
+        vec!["0.08", "0.12", "0.250", "3.0"],
+        execute_with_options(source, &options).unwrap()
+    );
+
+    let squares = "var x = 0.1d; var i = 0; while (i < 40) { x = x * x; i = i + 1; } x;";
+    assert_eq!(Some(&String::from("0")), execute(squares).unwrap().last());
+}
+
+#[test]
+fn errors_when_mixing_floats_with_exact_numbers() {
+    let error = (
+        String::from("Cannot implicitly mix floating-point and exact numbers."),
+        1,
+    );
+    assert_eq!(error, runtime_error(execute("1.5 + 1d;")));
+    assert_eq!(error, runtime_error(execute("1n == 1.0;")));
+    assert!(matches!(execute("1.5n;"), Err(Error::TokenizingError(_))));
+}
+
+#[test]
+fn runs_utf8_sources() {
+    let results = execute("var café = \"naïve ☕\"; // ünïcödé\ncafé + \"!\";").unwrap();
+    assert_eq!(vec!["nil", "\"naïve ☕!\""], results);
+}
+
+#[test]
+fn returns_all_parse_errors() {
+    assert_eq!(
+        "Parse Error: Expect ';' after value. at line 1\n\
+         Parse Error: Expect expression. at line 1",
+        execute("print 1 print 2; print );")
+            .unwrap_err()
+            .to_string()
+    );
+}
+
+#[test]
+fn rejects_invalid_assignment_targets() {
+    assert_eq!(
+        "Parse Error: Invalid assignment target. at line 2",
+        execute("var a = 1;\n1 + a = 2;").unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn runtime_errors_carry_the_call_stack() {
+    let error = execute("var a = 1;\nprint a;\nprint -\"a\";").unwrap_err();
+    assert!(matches!(
+        &error,
+        Error::InterpreterError { frames, .. } if frames == &[Frame {
+            function: String::from("<script>"),
+            line: 3,
+        }]
+    ));
+}
+
+#[test]
+fn errors_expose_codes_spans_and_sources() {
+    use std::error::Error as _;
+
+    let error = execute("print 1;\nprint 1 / 0;").unwrap_err();
+    assert_eq!(Some("E0202"), error.code());
+    assert_eq!(70, error.exit_code());
+    assert_eq!(2, error.span().unwrap().line);
+    assert_eq!(
+        "Runtime error at line 2: Tried dividing by zero!",
+        error.to_string()
+    );
+    let source = error.source().unwrap();
+    assert_eq!(
+        Some(&interpreter::Error::DivisionByZeroError),
+        source.downcast_ref::<interpreter::Error>()
+    );
+
+    let error = execute("print (;").unwrap_err();
+    assert_eq!(Some("E0102"), error.code());
+    assert_eq!(65, error.exit_code());
+    assert_eq!(8, error.span().unwrap().column);
+}
@@ -0,0 +1,117 @@
+use std::fs;
+
+use loxc::line_editor::{read_key, Input, Key, LineEditor};
+use loxc::repl::Repl;
+use loxc::Options;
+
+/// Types `keys` into `editor`, returning what it read.
+fn type_keys(editor: &LineEditor, completions: &[&str], keys: &str) -> (Input, String) {
+    let completions: Vec<String> = completions.iter().map(|word| word.to_string()).collect();
+    let mut out = Vec::new();
+    let input = editor
+        .read_line("> ", &completions, &mut keys.as_bytes(), &mut out)
+        .unwrap();
+    (input, String::from_utf8(out).unwrap())
+}
+
+fn line(text: &str) -> Input {
+    Input::Line(text.to_string())
+}
+
+#[test]
+fn decodes_keys_and_escape_sequences() {
+    let mut input = "a☕\x1b[D\x1b[3~\x1bOH\x7f\r".as_bytes();
+    let mut keys = Vec::new();
+    while let Some(key) = read_key(&mut input).unwrap() {
+        keys.push(key);
+    }
+    assert_eq!(
+        vec![
+            Key::Char('a'),
+            Key::Char('☕'),
+            Key::Left,
+            Key::Delete,
+            Key::Home,
+            Key::Backspace,
+            Key::Enter
+        ],
+        keys
+    );
+}
+
+#[test]
+fn edits_in_the_middle_of_the_line() {
+    let editor = LineEditor::default();
+    assert_eq!(
+        line("print 12;"),
+        type_keys(&editor, &[], "prit 2;\x1b[D\x1b[D1\x01\x06\x06\x06n\r").0
+    );
+    assert_eq!(line("ab"), type_keys(&editor, &[], "xab\x01\x1b[3~\r").0);
+    assert_eq!(line("ok"), type_keys(&editor, &[], "junk\x15ok\r").0);
+    assert_eq!(Input::Interrupted, type_keys(&editor, &[], "junk\x03").0);
+    assert_eq!(Input::Eof, type_keys(&editor, &[], "\x04").0);
+}
+
+#[test]
+fn browses_and_searches_history() {
+    let mut editor = LineEditor::default();
+    for entry in ["var a = 1;", "print a;", "var b = 2;"] {
+        editor.add_history(entry).unwrap();
+    }
+
+    assert_eq!(
+        line("print a;"),
+        type_keys(&editor, &[], "\x1b[A\x1b[A\r").0
+    );
+    assert_eq!(line("x"), type_keys(&editor, &[], "x\x1b[A\x1b[B\r").0);
+    assert_eq!(
+        line("var a = 1;"),
+        type_keys(&editor, &[], "\x12var\x12\r").0
+    );
+
+    let (input, out) = type_keys(&editor, &[], "\x12pri\x1b[C!\r");
+    assert_eq!(line("print a;!"), input);
+    assert!(out.contains("(reverse-i-search)`pri': print a;"));
+}
+
+#[test]
+fn completes_words_before_the_cursor() {
+    let editor = LineEditor::default();
+    let words = ["print", "var", "value", "values"];
+
+    assert_eq!(line("print 1;"), type_keys(&editor, &words, "pr\t 1;\r").0);
+    assert_eq!(line("va"), type_keys(&editor, &words, "v\t\r").0);
+    let (input, out) = type_keys(&editor, &words, "va\t\r");
+    assert_eq!(line("va"), input);
+    assert!(out.contains("\nvar  value  values\n"));
+}
+
+#[test]
+fn persists_history() {
+    let path = std::env::temp_dir().join(format!("loxc-history-{}", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut editor = LineEditor::with_history_file(path.clone()).unwrap();
+    editor.add_history("1 + 1;").unwrap();
+    editor.add_history("1 + 1;").unwrap();
+    editor.add_history("  ").unwrap();
+    editor.add_history("print 2;").unwrap();
+
+    let editor = LineEditor::with_history_file(path.clone()).unwrap();
+    fs::remove_file(path).unwrap();
+    assert_eq!(vec!["1 + 1;", "print 2;"], editor.history);
+}
+
+#[test]
+fn completes_keywords_and_defined_variables() {
+    let mut repl = Repl::new(Options::default());
+    repl.feed("var counter = 1;", &mut Vec::new(), &mut Vec::new())
+        .unwrap();
+    repl.feed("total = 5;", &mut Vec::new(), &mut Vec::new())
+        .unwrap();
+
+    let completions = repl.completions();
+    assert!(completions.contains(&String::from("counter")));
+    assert!(completions.contains(&String::from("total")));
+    assert!(completions.contains(&String::from("while")));
+}
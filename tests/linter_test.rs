@@ -0,0 +1,147 @@
+use loxc::linter::{lint, LintConfig, Rule};
+use loxc::Error;
+
+fn rules(source: &str) -> Vec<(Rule, usize)> {
+    lint(source, &LintConfig::default())
+        .unwrap()
+        .iter()
+        .map(|lint| (lint.rule, lint.span.line))
+        .collect()
+}
+
+#[test]
+fn reports_unused_and_unread_variables() {
+    let source = "var a = 1;
+var b = 2;
+var c;
+c = b;
+{
+    var d;
+    print a;
+}
+for (var i = 0; i < 3; i = i + 1) {}
+";
+    assert_eq!(
+        vec![(Rule::UnreadVariable, 3), (Rule::UnusedVariable, 6),],
+        rules(source)
+    );
+}
+
+#[test]
+fn reports_shadowed_names() {
+    let source = "var a = 1;
+{
+    var a = a + 1;
+    print a;
+    for (var a = 0; a < 1; a = a + 1) print a;
+}
+";
+    let lints = lint(source, &LintConfig::default()).unwrap();
+    let shadowed: Vec<usize> = lints
+        .iter()
+        .filter(|lint| lint.rule == Rule::ShadowedName)
+        .map(|lint| lint.span.line)
+        .collect();
+    assert_eq!(vec![3, 5], shadowed);
+    assert_eq!(
+        Some("`a` was first declared on line 3"),
+        lints[1].help.as_deref()
+    );
+}
+
+#[test]
+fn reports_constant_conditions() {
+    let source = "var a = 1;
+if (a) print 1;
+if (-1 + 2 > 0) print 2;
+while (true) a = nil;
+for (;;) print a;
+for (; false;) print a;
+if ((a = 2)) print a;
+";
+    assert_eq!(
+        vec![
+            (Rule::ConstantCondition, 3),
+            (Rule::ConstantCondition, 4),
+            (Rule::ConstantCondition, 6),
+        ],
+        rules(source)
+    );
+}
+
+#[test]
+fn reports_self_assignments_and_comparisons() {
+    let source = "var a = 1;
+a = a;
+print a == a;
+print (a + 1) <= (a + 1);
+print a + a;
+print a == b;
+print (a = 1) == (a = 1);
+";
+    let lints = lint(source, &LintConfig::default()).unwrap();
+    let found: Vec<(Rule, usize, &str)> = lints
+        .iter()
+        .map(|lint| (lint.rule, lint.span.line, lint.message.as_str()))
+        .collect();
+    assert_eq!(
+        vec![
+            (Rule::SelfAssignment, 2, "`a` is assigned to itself."),
+            (Rule::SelfComparison, 3, "`a` is compared to itself."),
+            (Rule::SelfComparison, 4, "`(a + 1)` is compared to itself."),
+        ],
+        found
+    );
+}
+
+#[test]
+fn rules_can_be_disabled_and_enabled() {
+    let source = "var a = 1;\na = a;\nvar b;\n";
+    let mut config = LintConfig::default();
+    config.disable(Rule::UnusedVariable);
+    let lints = lint(source, &config).unwrap();
+    assert_eq!(
+        vec![Rule::SelfAssignment],
+        lints.iter().map(|l| l.rule).collect::<Vec<_>>()
+    );
+
+    config.enabled.clear();
+    assert!(lint(source, &config).unwrap().is_empty());
+    config.enable(Rule::UnusedVariable);
+    assert_eq!(1, lint(source, &config).unwrap().len());
+
+    assert_eq!(Ok(Rule::SelfComparison), "self-comparison".parse());
+    assert!("typo".parse::<Rule>().is_err());
+}
+
+#[test]
+fn lox_ignore_comments_silence_lints() {
+    let source = "var a = 1; // lox-ignore
+// lox-ignore: self-assignment, shadowed-name
+var b = 2;
+// lox-ignore: shadowed-name
+var c;
+var d; // lox-ignored
+";
+    assert_eq!(
+        vec![
+            (Rule::UnusedVariable, 3),
+            (Rule::UnusedVariable, 5),
+            (Rule::UnusedVariable, 6)
+        ],
+        rules(source)
+    );
+}
+
+#[test]
+fn rejects_invalid_source() {
+    let config = LintConfig::default();
+    assert!(matches!(
+        lint("print (;", &config),
+        Err(Error::ParseError(_))
+    ));
+    assert!(matches!(
+        lint("var $;", &config),
+        Err(Error::TokenizingError(_))
+    ));
+}
@@ -0,0 +1,427 @@
+use std::io::Cursor;
+
+use loxc::json::Json;
+use loxc::lsp::{self, Server};
+
+const URI: &str = "file:///test.lox";
+
+/// Drives a [`Server`] the way an editor would.
+struct Client {
+    server: Server,
+    next_id: i64,
+    /// Notifications received from the server, oldest first.
+    notifications: Vec<Json>,
+}
+
+impl Client {
+    fn new() -> Client {
+        let mut client = Client {
+            server: Server::default(),
+            next_id: 1,
+            notifications: Vec::new(),
+        };
+        client.request("initialize", "{}");
+        client.notify("initialized", "{}");
+        client
+    }
+
+    /// Sends a request and returns the whole response.
+    fn send(&mut self, method: &str, params: &str) -> Json {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+            id, method, params
+        );
+        let mut response = None;
+        for reply in self.server.handle_text(&message) {
+            if reply.get("id").is_some() {
+                assert_eq!(Some(id), reply.get("id").and_then(Json::as_i64));
+                response = Some(reply);
+            } else {
+                self.notifications.push(reply);
+            }
+        }
+        response.expect("no response")
+    }
+
+    /// Sends a request and returns its result.
+    fn request(&mut self, method: &str, params: &str) -> Json {
+        let response = self.send(method, params);
+        assert!(response.get("error").is_none(), "{}", response);
+        response.get("result").cloned().unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: &str) {
+        let message = format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#,
+            method, params
+        );
+        let replies = self.server.handle_text(&message);
+        self.notifications.extend(replies);
+    }
+
+    fn open(&mut self, text: &str) {
+        let params = Json::object(vec![(
+            "textDocument",
+            Json::object(vec![
+                ("uri", URI.into()),
+                ("languageId", "lox".into()),
+                ("version", 1_i64.into()),
+                ("text", text.into()),
+            ]),
+        )]);
+        self.notify("textDocument/didOpen", &params.to_string());
+    }
+
+    /// Sends a request about the position `line`:`character` of the test
+    /// document.
+    fn at(&mut self, method: &str, line: usize, character: usize) -> Json {
+        let params = format!(
+            r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}},"context":{{"includeDeclaration":true}}}}"#,
+            URI, line, character
+        );
+        self.request(method, &params)
+    }
+
+    /// The diagnostics of the last `publishDiagnostics` notification.
+    fn diagnostics(&self) -> Vec<Json> {
+        let notification = self.notifications.last().expect("no notification");
+        assert_eq!(
+            Some("textDocument/publishDiagnostics"),
+            notification.get("method").and_then(Json::as_str)
+        );
+        let params = notification.get("params").unwrap();
+        assert_eq!(Some(URI), params.get("uri").and_then(Json::as_str));
+        params
+            .get("diagnostics")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .to_vec()
+    }
+}
+
+/// `(line, character)` of the start of a range.
+fn start(range: &Json) -> (i64, i64) {
+    let start = range.get("start").unwrap();
+    (
+        start.get("line").and_then(Json::as_i64).unwrap(),
+        start.get("character").and_then(Json::as_i64).unwrap(),
+    )
+}
+
+#[test]
+fn initializes_with_capabilities() {
+    let mut server = Server::default();
+    let replies =
+        server.handle_text(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#);
+    let capabilities = replies[0]
+        .get("result")
+        .and_then(|result| result.get("capabilities"))
+        .unwrap();
+    for capability in [
+        "definitionProvider",
+        "referencesProvider",
+        "hoverProvider",
+        "documentSymbolProvider",
+    ] {
+        assert_eq!(
+            Some(true),
+            capabilities.get(capability).and_then(Json::as_bool)
+        );
+    }
+    assert_eq!(
+        Some(1),
+        capabilities.get("textDocumentSync").and_then(Json::as_i64)
+    );
+    assert!(capabilities.get("semanticTokensProvider").is_some());
+}
+
+#[test]
+fn publishes_diagnostics_on_open_change_and_close() {
+    let mut client = Client::new();
+    client.open("var a = 1;\nprint a +;\n");
+    let diagnostics = client.diagnostics();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(
+        Some("E0102"),
+        diagnostics[0].get("code").and_then(Json::as_str)
+    );
+    assert_eq!(
+        Some(1),
+        diagnostics[0].get("severity").and_then(Json::as_i64)
+    );
+    assert_eq!((1, 9), start(diagnostics[0].get("range").unwrap()));
+
+    client.notify(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"text":"print \"☃\" @;"}}]}}"#,
+            URI
+        ),
+    );
+    let diagnostics = client.diagnostics();
+    assert_eq!(
+        Some("E0001"),
+        diagnostics[0].get("code").and_then(Json::as_str)
+    );
+    // Columns count UTF-16 code units.
+    assert_eq!((0, 10), start(diagnostics[0].get("range").unwrap()));
+
+    client.notify(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":3}},"contentChanges":[{{"text":"print 1;"}}]}}"#,
+            URI
+        ),
+    );
+    assert!(client.diagnostics().is_empty());
+
+    client.notify(
+        "textDocument/didClose",
+        &format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI),
+    );
+    assert!(client.diagnostics().is_empty());
+}
+
+#[test]
+fn survives_deeply_nested_documents() {
+    // Runs on a thread of the default size, like the server's own.
+    std::thread::spawn(|| {
+        let mut client = Client::new();
+        client.open(&format!("print 1{};", "+1".repeat(100_000)));
+        let diagnostics = client.diagnostics();
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(
+            Some("E0105"),
+            diagnostics[0].get("code").and_then(Json::as_str)
+        );
+
+        client.open(&format!("var a = 1;\nprint {}a;", "a + ".repeat(200)));
+        assert!(client.diagnostics().is_empty());
+        let references = client.at("textDocument/references", 0, 4);
+        assert_eq!(202, references.as_array().unwrap().len());
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn finds_definitions_and_references() {
+    let mut client = Client::new();
+    client.open("var a = 1;\n{\n  var a = 2;\n  a = a + 1;\n}\nprint a;\n");
+
+    let definition = client.at("textDocument/definition", 3, 6);
+    assert_eq!(Some(URI), definition.get("uri").and_then(Json::as_str));
+    assert_eq!((2, 6), start(definition.get("range").unwrap()));
+
+    let definition = client.at("textDocument/definition", 5, 7);
+    assert_eq!((0, 4), start(definition.get("range").unwrap()));
+
+    let references = client.at("textDocument/references", 0, 4);
+    let starts: Vec<(i64, i64)> = references
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| start(location.get("range").unwrap()))
+        .collect();
+    assert_eq!(vec![(0, 4), (5, 6)], starts);
+
+    let references = client.at("textDocument/references", 2, 6);
+    assert_eq!(3, references.as_array().unwrap().len());
+
+    assert_eq!(Json::Null, client.at("textDocument/definition", 0, 0));
+
+    // Statements around syntax and tokenizer errors still resolve.
+    client.open("var a = 1;\nprint a +;\nprint a @;\nprint a;\n");
+    let references = client.at("textDocument/references", 0, 4);
+    assert_eq!(3, references.as_array().unwrap().len());
+}
+
+#[test]
+fn hovers_with_the_declaration() {
+    let mut client = Client::new();
+    client.open("var greeting = \"hi\";\nprint greeting;\n");
+
+    let hover = client.at("textDocument/hover", 1, 8);
+    let contents = hover.get("contents").unwrap();
+    assert_eq!(
+        Some("markdown"),
+        contents.get("kind").and_then(Json::as_str)
+    );
+    assert_eq!(
+        Some("```lox\nvar greeting = \"hi\";\n```"),
+        contents.get("value").and_then(Json::as_str)
+    );
+    assert_eq!((1, 6), start(hover.get("range").unwrap()));
+
+    assert_eq!(Json::Null, client.at("textDocument/hover", 1, 2));
+}
+
+#[test]
+fn lists_document_symbols() {
+    let mut client = Client::new();
+    client.open("var a = 1;\nfor (var i = 0; i < 2; i = i + 1) {\n  var b;\n}\n");
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        &format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI),
+    );
+    let names: Vec<(&str, i64, (i64, i64))> = symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| {
+            (
+                symbol.get("name").and_then(Json::as_str).unwrap(),
+                symbol.get("kind").and_then(Json::as_i64).unwrap(),
+                start(symbol.get("selectionRange").unwrap()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![("a", 13, (0, 4)), ("i", 13, (1, 9)), ("b", 13, (2, 6))],
+        names
+    );
+}
+
+#[test]
+fn encodes_semantic_tokens() {
+    let mut client = Client::new();
+    client.open("var a = 1; // one\nprint \"x\ny\" + a;");
+
+    let tokens = client.request(
+        "textDocument/semanticTokens/full",
+        &format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI),
+    );
+    let data: Vec<i64> = tokens
+        .get("data")
+        .and_then(Json::as_array)
+        .unwrap()
+        .iter()
+        .map(|value| value.as_i64().unwrap())
+        .collect();
+    #[rustfmt::skip]
+    assert_eq!(
+        vec![
+            0, 0, 3, 0, 0, // var
+            0, 4, 1, 1, 1, // a, declared
+            0, 2, 1, 4, 0, // =
+            0, 2, 1, 3, 0, // 1
+            0, 3, 6, 5, 0, // // one
+            1, 0, 5, 0, 0, // print
+            0, 6, 2, 2, 0, // "x
+            1, 0, 2, 2, 0, // y"
+            0, 3, 1, 4, 0, // +
+            0, 2, 1, 1, 0, // a
+        ],
+        data
+    );
+}
+
+#[test]
+fn reports_protocol_errors() {
+    let mut client = Client::new();
+    let response = client.send("textDocument/unknown", "{}");
+    let code = |response: &Json| {
+        response
+            .get("error")
+            .and_then(|error| error.get("code"))
+            .and_then(Json::as_i64)
+    };
+    assert_eq!(Some(-32601), code(&response));
+
+    let response = client.send(
+        "textDocument/hover",
+        r#"{"textDocument":{"uri":"file:///nope.lox"}}"#,
+    );
+    assert_eq!(Some(-32602), code(&response));
+
+    let replies = client.server.handle_text("{not json");
+    assert_eq!(Some(-32700), code(&replies[0]));
+    assert_eq!(Some(&Json::Null), replies[0].get("id"));
+
+    for message in ["[1,2]", r#"{"id":1}"#, r#"{"id":{},"method":5}"#] {
+        let replies = client.server.handle_text(message);
+        assert_eq!(1, replies.len(), "{}", message);
+        assert_eq!(Some(-32600), code(&replies[0]));
+    }
+    let replies = client.server.handle_text(r#"{"id":1}"#);
+    assert_eq!(Some(1), replies[0].get("id").and_then(Json::as_i64));
+    assert!(client
+        .server
+        .handle_text(r#"{"jsonrpc":"2.0","id":7,"result":null}"#)
+        .is_empty());
+
+    assert_eq!(Json::Null, client.request("shutdown", "null"));
+    assert_eq!(Some(-32600), code(&client.send("initialize", "{}")));
+}
+
+#[test]
+fn serves_framed_messages_until_exit() {
+    let messages = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///a.lox","text":"print ;"}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+    ];
+    let input: String = messages
+        .iter()
+        .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+        .collect();
+    let mut output = Vec::new();
+    assert!(lsp::run(Cursor::new(input), &mut output).unwrap());
+
+    let mut output = Cursor::new(output);
+    let mut replies = Vec::new();
+    while let Some(message) = lsp::read_message(&mut output).unwrap() {
+        replies.push(message.parse::<Json>().unwrap());
+    }
+    assert_eq!(3, replies.len());
+    assert_eq!(Some(1), replies[0].get("id").and_then(Json::as_i64));
+    assert_eq!(
+        Some("textDocument/publishDiagnostics"),
+        replies[1].get("method").and_then(Json::as_str)
+    );
+    assert_eq!(Some(2), replies[2].get("id").and_then(Json::as_i64));
+
+    let exit_early = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+    let input = format!("Content-Length: {}\r\n\r\n{}", exit_early.len(), exit_early);
+    assert!(!lsp::run(Cursor::new(input), Vec::new()).unwrap());
+
+    for input in [
+        "Content-Length: 99999999999999999\r\n\r\n{}",
+        "Content-Length: 3\r\n\r\n{}",
+    ] {
+        let error = lsp::read_message(&mut Cursor::new(input)).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+    }
+}
+
+#[test]
+fn parses_and_prints_json() {
+    let text = r#" {"a": [1, -2.5e1, true, null], "b": "\"\u00e9\ud83d\ude00\n"} "#;
+    let json: Json = text.parse().unwrap();
+    assert_eq!(
+        Some(1),
+        json.get("a")
+            .and_then(|a| a.as_array())
+            .and_then(|a| a[0].as_i64())
+    );
+    assert_eq!(
+        Some(&Json::Float(-25.0)),
+        json.get("a").and_then(|a| a.as_array()).map(|a| &a[1])
+    );
+    assert_eq!(Some("\"é😀\n"), json.get("b").and_then(Json::as_str));
+    assert_eq!(
+        r#"{"a":[1,-25.0,true,null],"b":"\"é😀\n"}"#,
+        json.to_string()
+    );
+
+    for invalid in ["", "[1,]", "{\"a\" 1}", "\"\\ud800\"", "[1] 2", "nul"] {
+        assert!(invalid.parse::<Json>().is_err(), "{}", invalid);
+    }
+    assert!("[".repeat(1000).parse::<Json>().is_err());
+}
@@ -0,0 +1,66 @@
+use std::fs;
+
+use loxc::repl::{is_complete, Repl};
+use loxc::Options;
+
+/// Runs `input` through a fresh session, returning stdout and stderr.
+fn session(input: &str) -> (String, String) {
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    Repl::new(Options::default())
+        .run(input.as_bytes(), &mut out, &mut err)
+        .unwrap();
+    (
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+#[test]
+fn keeps_state_between_inputs_and_echoes_expressions() {
+    let (out, _) = session("var a = 1;\na + 1;\na = 5;\na;\n");
+    assert_eq!("2\n5\n5\n", out);
+}
+
+#[test]
+fn waits_for_unclosed_input() {
+    assert!(!is_complete("{ var a = (1 +\n"));
+    assert!(!is_complete("\"abc\n"));
+    assert!(is_complete("{ (1); }\n"));
+    assert!(is_complete("print );\n"));
+
+    let (out, err) = session("{\nvar a = (1\n+ 2);\n}\na * 2;\n");
+    assert_eq!("6\n", out);
+    assert_eq!("> ... ... ... > > \n", err);
+}
+
+#[test]
+fn reports_errors_and_carries_on() {
+    let (out, err) = session("1 +;\n-\"a\";\n1 + 1;\n");
+    assert_eq!("2\n", out);
+    assert!(err.contains("error[E0102]: Expect expression.\n --> <repl>:1:4\n"));
+    assert!(err.contains("File \"<repl>\", line 1, in <script>\n"));
+    assert!(err.contains("RuntimeError: Operand must be a number.\n"));
+}
+
+#[test]
+fn runs_meta_commands() {
+    let path = std::env::temp_dir().join(format!("loxc-repl-load-{}.lox", std::process::id()));
+    fs::write(&path, "var b = \"x\";\nb;\n").unwrap();
+
+    let input = format!(
+        "var a = 2;\nc = 3;\n:load {}\n:env\n:reset\n:env\na;\n:quit\n1;\n",
+        path.display()
+    );
+    let (out, err) = session(&input);
+    fs::remove_file(path).unwrap();
+
+    assert_eq!("3\na = 2\nb = \"x\"\nc = 3\n", out);
+    assert!(err.contains("Undefined variable 'a'."));
+}
+
+#[test]
+fn rejects_unknown_meta_commands() {
+    let (_, err) = session(":frobnicate\n");
+    assert!(err.contains("Unknown command: :frobnicate"));
+}
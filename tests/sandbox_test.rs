@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+
+use loxc::{execute_with_options, Error, Options, Sandbox};
+
+fn sandboxed(sandbox: Sandbox) -> Options {
+    Options {
+        sandbox,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn stops_infinite_loops_after_the_step_limit() {
+    let options = sandboxed(Sandbox {
+        max_steps: Some(10_000),
+        ..Default::default()
+    });
+
+    assert!(matches!(
+        execute_with_options("while (true) {}", &options).err(),
+        Some(Error::StepLimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn allows_scripts_within_the_step_limit() {
+    let options = sandboxed(Sandbox {
+        max_steps: Some(10_000),
+        ..Default::default()
+    });
+
+    assert!(execute_with_options("var i = 0; while (i < 10) i = i + 1;", &options).is_ok());
+}
+
+#[test]
+fn stops_infinite_loops_at_the_deadline() {
+    let options = sandboxed(Sandbox {
+        timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+    });
+
+    let start = Instant::now();
+    assert!(matches!(
+        execute_with_options("while (true) {}", &options).err(),
+        Some(Error::Timeout { .. })
+    ));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn limits_string_length() {
+    let options = sandboxed(Sandbox {
+        max_string_length: Some(8),
+        ..Default::default()
+    });
+
+    assert!(execute_with_options("\"abcd\" + \"efgh\";", &options).is_ok());
+    assert!(matches!(
+        execute_with_options("var s = \"a\"; while (true) s = s + s;", &options).err(),
+        Some(Error::StringTooLong { .. })
+    ));
+}
+
+#[test]
+fn limits_heap_size() {
+    let options = sandboxed(Sandbox {
+        max_heap_size: Some(4096),
+        ..Default::default()
+    });
+
+    let error =
+        execute_with_options("var s = \"a\";\nwhile (true) s = s + s;", &options).unwrap_err();
+    assert!(matches!(error, Error::HeapLimitExceeded { .. }));
+    assert_eq!(Some(2), error.span().map(|span| span.line));
+    assert_eq!("Heap size limit exceeded at line 2.", error.to_string());
+}
+
+#[test]
+fn limits_the_size_of_exact_numbers() {
+    let options = sandboxed(Sandbox {
+        max_heap_size: Some(4096),
+        timeout: Some(Duration::from_millis(500)),
+        ..Default::default()
+    });
+
+    let start = Instant::now();
+    for source in [
+        "var x = 3n; while (true) x = x * x;",
+        "var x = 3.5d; while (true) x = x * x;",
+    ] {
+        assert!(
+            matches!(
+                execute_with_options(source, &options).err(),
+                Some(Error::HeapLimitExceeded { .. })
+            ),
+            "{}",
+            source
+        );
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(execute_with_options(
+        "var x = 3n; var i = 0; while (i < 10) { x = x * x; i = i + 1; }",
+        &options
+    )
+    .is_ok());
+}
+
+#[test]
+fn limits_the_cost_of_aligning_decimal_scales() {
+    let options = sandboxed(Sandbox {
+        max_heap_size: Some(1024),
+        ..Default::default()
+    });
+
+    let tiny = format!("var x = 0.{}1d;", "0".repeat(5000));
+    for operation in ["x == 1d;", "x < 1d;", "x + 1d;"] {
+        assert!(
+            matches!(
+                execute_with_options(&format!("{} {}", tiny, operation), &options).err(),
+                Some(Error::HeapLimitExceeded { .. })
+            ),
+            "{}",
+            operation
+        );
+    }
+    assert!(execute_with_options(&format!("{} x == x;", tiny), &options).is_ok());
+
+    let options = sandboxed(Sandbox {
+        timeout: Some(Duration::from_millis(500)),
+        ..Default::default()
+    });
+    let start = Instant::now();
+    assert!(matches!(
+        execute_with_options(
+            "var x = 0.1d; while (true) { x = x * x; x == 1d; }",
+            &options
+        )
+        .err(),
+        Some(Error::Timeout { .. })
+    ));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn garbage_does_not_count_against_the_heap_limit() {
+    let options = sandboxed(Sandbox {
+        max_heap_size: Some(4096),
+        ..Default::default()
+    });
+
+    assert!(execute_with_options(
+        "var i = 0; var s = \"\"; while (i < 1000) { s = \"ab\" + \"cd\"; i = i + 1; }",
+        &options
+    )
+    .is_ok());
+}
@@ -8,7 +8,7 @@ fn tokenizes_a_empty_source() {
         ..Default::default()
     };
 
-    assert_eq!("0", tokenizer.scan_tokens().to_string());
+    assert!(tokenizer.scan_tokens().is_ok());
     assert_eq!(TokenType::EOF, tokenizer.tokens[0].token_type);
 }
 
@@ -19,7 +19,127 @@ fn tokenizes_numbers() {
         ..Default::default()
     };
 
-    assert_eq!("0", tokenizer.scan_tokens().to_string());
+    assert!(tokenizer.scan_tokens().is_ok());
 
     assert_eq!(TokenType::Number, tokenizer.tokens[0].token_type);
 }
+
+#[test]
+fn tokenizes_integer_literals() {
+    let mut tokenizer = Tokenizer {
+        source: "42 0x2A 0b10_1010 4.2",
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_ok());
+
+    let literals: Vec<String> = tokenizer.tokens[..4]
+        .iter()
+        .map(|token| token.literal.as_ref().unwrap().to_string())
+        .collect();
+    assert_eq!(vec!["42.0", "42.0", "42.0", "4.2"], literals);
+}
+
+#[test]
+fn tokenizes_exact_number_suffixes() {
+    let mut tokenizer = Tokenizer {
+        source: "12n 0xFFn 1.50d 7d 3name",
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_ok());
+
+    let lexemes: Vec<String> = tokenizer.tokens[..6]
+        .iter()
+        .map(|token| token.lexeme.to_string())
+        .collect();
+    assert_eq!(vec!["12n", "0xFFn", "1.50d", "7d", "3", "name"], lexemes);
+}
+
+#[test]
+fn tokenizes_unicode_identifiers() {
+    let mut tokenizer = Tokenizer {
+        source: "café _变量1 x\u{303} ñ",
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_ok());
+
+    let lexemes: Vec<String> = tokenizer.tokens[..4]
+        .iter()
+        .map(|token| token.lexeme.to_string())
+        .collect();
+    assert_eq!(vec!["café", "_变量1", "x\u{303}", "ñ"], lexemes);
+    assert!(tokenizer.tokens[..4]
+        .iter()
+        .all(|token| token.token_type == TokenType::Identifier));
+}
+
+#[test]
+fn measures_columns_in_characters() {
+    let mut tokenizer = Tokenizer {
+        source: "\"☕☕\" é +\n  // ünï\n  x",
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_ok());
+
+    let positions: Vec<(usize, usize)> = tokenizer
+        .tokens
+        .iter()
+        .map(|token| (token.span.line, token.span.column))
+        .collect();
+    assert_eq!(vec![(1, 1), (1, 6), (1, 8), (3, 3), (3, 4)], positions);
+}
+
+#[test]
+fn measures_columns_after_multi_line_strings() {
+    let mut tokenizer = Tokenizer {
+        source: "x = \"a\nbé\" ; y",
+        keep_trivia: true,
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_ok());
+
+    let positions: Vec<(usize, usize)> = tokenizer
+        .tokens
+        .iter()
+        .map(|token| (token.span.line, token.span.column))
+        .collect();
+    assert_eq!(
+        vec![(1, 1), (1, 3), (1, 5), (2, 5), (2, 7), (2, 8)],
+        positions
+    );
+}
+
+#[test]
+fn rejects_non_identifier_characters() {
+    let mut tokenizer = Tokenizer {
+        source: "1 € 2",
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_err());
+    assert_eq!(3, tokenizer.tokens.len());
+}
+
+#[test]
+fn records_byte_spans() {
+    let mut tokenizer = Tokenizer {
+        source: "é = \"a\nb\";",
+        ..Default::default()
+    };
+
+    assert!(tokenizer.scan_tokens().is_ok());
+
+    let spans: Vec<(usize, usize, usize)> = tokenizer
+        .tokens
+        .iter()
+        .map(|token| (token.span.start, token.span.end, token.span.line))
+        .collect();
+    assert_eq!(
+        vec![(0, 2, 1), (3, 4, 1), (5, 10, 1), (10, 11, 2), (11, 11, 2)],
+        spans
+    );
+}